@@ -0,0 +1,15 @@
+ALTER TABLE players ADD COLUMN season_points INT NOT NULL DEFAULT 0;
+ALTER TABLE players ADD COLUMN slots_completed INT NOT NULL DEFAULT 0;
+ALTER TABLE players ADD COLUMN goals_reached INT NOT NULL DEFAULT 0;
+
+CREATE TABLE seasons (
+  id INTEGER PRIMARY KEY,
+  name TEXT UNIQUE NOT NULL,
+  started INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+
+CREATE TABLE season_standings (
+  season INTEGER NOT NULL REFERENCES seasons(id) ON DELETE CASCADE,
+  player INTEGER NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+  points INTEGER NOT NULL
+) STRICT;
@@ -0,0 +1,65 @@
+ALTER TABLE tracked_worlds ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tracked_slots ADD COLUMN goal_timestamp INTEGER;
+
+DROP VIEW worlds_overview;
+DROP VIEW unclaimed_slots;
+DROP VIEW current_realities;
+DROP VIEW current_claims;
+DROP VIEW current_realities_claims;
+DROP VIEW sheets_push;
+DROP VIEW public_claims;
+
+CREATE VIEW worlds_overview (id, name, reality, unclaimed, unstarted, in_progress, goal, all_checks, done) AS
+  SELECT tracked_worlds.id, tracked_worlds.name, realities.name,
+  COUNT(*) FILTER (WHERE claims.player IS NULL),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 0),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 1),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 2),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 3),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 4)
+  FROM tracked_worlds INNER JOIN tracked_slots ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN claims ON claims.slot = tracked_slots.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE archived = 0
+  GROUP BY tracked_worlds.name
+  ORDER BY tracked_worlds.id;
+
+CREATE VIEW unclaimed_slots (world, reality, slot, games, free)
+  AS SELECT tracked_worlds.name, realities.name, tracked_slots.name, tracked_slots.games, tracked_slots.free
+  FROM tracked_slots INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE tracked_slots.id NOT IN (SELECT slot FROM claims) AND archived = 0 ORDER BY tracked_worlds.id DESC;
+
+CREATE VIEW current_realities (player, reality)
+  AS SELECT DISTINCT claims.player, tracked_worlds.reality FROM claims
+  LEFT JOIN tracked_slots ON claims.slot = tracked_slots.id
+  LEFT JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  WHERE (free = 0 OR free IS NULL) AND (status < 2 OR status IS NULL) AND tracked_worlds.reality IS NOT NULL AND archived = 0;
+
+CREATE VIEW current_claims (player, reality, claims)
+  AS SELECT claims.player, tracked_worlds.reality, COUNT(*) FROM claims
+  LEFT JOIN tracked_slots ON claims.slot = tracked_slots.id
+  LEFT JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  WHERE (free = 0 OR free IS NULL) AND (status < 2 OR status IS NULL) AND (archived = 0 OR archived IS NULL)
+  GROUP BY claims.player, tracked_worlds.reality;
+
+CREATE VIEW current_realities_claims (player, realities)
+  AS SELECT claims.player, COUNT(DISTINCT tracked_worlds.reality) FROM claims
+  LEFT JOIN tracked_slots ON claims.slot = tracked_slots.id
+  LEFT JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE (free = 0 OR free IS NULL) AND (status < 2 OR status IS NULL) AND tracked_worlds.reality IS NOT NULL AND external = 0 AND archived = 0
+  GROUP BY claims.player;
+
+CREATE VIEW sheets_push (world, slot, status, free, player)
+  AS SELECT tracked_worlds.name, tracked_slots.name, status, free, players.name FROM tracked_worlds
+  INNER JOIN tracked_slots ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN claims ON claims.slot = tracked_slots.id
+  LEFT JOIN players ON claims.player = players.id
+  WHERE archived = 0;
+
+CREATE VIEW public_claims (world, slot, description)
+  AS SELECT tracked_worlds.name, tracked_slots.name, public FROM claims
+    INNER JOIN tracked_slots ON claims.slot = tracked_slots.id
+    INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+    WHERE public IS NOT NULL AND archived = 0;
@@ -0,0 +1,2 @@
+ALTER TABLE tracked_worlds ADD COLUMN archived_at INTEGER;
+ALTER TABLE tracked_slots ADD COLUMN awarded_to INTEGER REFERENCES players(id);
@@ -0,0 +1,9 @@
+CREATE TABLE undo_log (
+  id INTEGER PRIMARY KEY,
+  actor INTEGER NOT NULL,
+  command TEXT NOT NULL,
+  description TEXT NOT NULL,
+  payload TEXT NOT NULL,
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  undone INTEGER NOT NULL DEFAULT 0
+) STRICT;
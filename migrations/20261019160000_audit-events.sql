@@ -0,0 +1,20 @@
+CREATE TABLE audit_events (
+  id INTEGER PRIMARY KEY,
+  actor INTEGER NOT NULL,
+  command TEXT NOT NULL,
+  world TEXT,
+  slot TEXT,
+  target INTEGER,
+  outcome TEXT NOT NULL,
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+
+CREATE INDEX audit_events_timestamp ON audit_events (timestamp);
+
+CREATE TABLE audit_filters (
+  id INTEGER PRIMARY KEY,
+  player INTEGER,
+  world TEXT,
+  after INTEGER,
+  before INTEGER
+) STRICT;
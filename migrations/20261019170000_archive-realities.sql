@@ -0,0 +1,34 @@
+ALTER TABLE realities ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+
+DROP VIEW worlds_overview;
+DROP VIEW unclaimed_slots;
+DROP VIEW current_realities_claims;
+
+CREATE VIEW worlds_overview (id, name, reality, unclaimed, unstarted, in_progress, goal, all_checks, done) AS
+  SELECT tracked_worlds.id, tracked_worlds.name, realities.name,
+  COUNT(*) FILTER (WHERE claims.player IS NULL),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 0),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 1),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 2),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 3),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 4)
+  FROM tracked_worlds INNER JOIN tracked_slots ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN claims ON claims.slot = tracked_slots.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE tracked_worlds.archived = 0
+  GROUP BY tracked_worlds.name
+  ORDER BY tracked_worlds.id;
+
+CREATE VIEW unclaimed_slots (world, reality, slot, games, free)
+  AS SELECT tracked_worlds.name, realities.name, tracked_slots.name, tracked_slots.games, tracked_slots.free
+  FROM tracked_slots INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE tracked_slots.id NOT IN (SELECT slot FROM claims) AND tracked_worlds.archived = 0 ORDER BY tracked_worlds.id DESC;
+
+CREATE VIEW current_realities_claims (player, realities)
+  AS SELECT claims.player, COUNT(DISTINCT tracked_worlds.reality) FROM claims
+  LEFT JOIN tracked_slots ON claims.slot = tracked_slots.id
+  LEFT JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE (free = 0 OR free IS NULL) AND (status < 2 OR status IS NULL) AND tracked_worlds.reality IS NOT NULL AND external = 0 AND tracked_worlds.archived = 0
+  GROUP BY claims.player;
@@ -0,0 +1,17 @@
+ALTER TABLE realities ADD COLUMN min_completed INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE realities ADD COLUMN unclaim_cooldown INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE realities ADD COLUMN points_per_claim INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE reality_game_caps (
+  reality INTEGER NOT NULL REFERENCES realities(id),
+  game TEXT NOT NULL,
+  max_claims INTEGER NOT NULL,
+  PRIMARY KEY (reality, game)
+) STRICT;
+
+CREATE TABLE released_claims (
+  id INTEGER PRIMARY KEY,
+  player INTEGER NOT NULL REFERENCES players(id),
+  reality INTEGER REFERENCES realities(id),
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
@@ -0,0 +1,9 @@
+CREATE TABLE claim_grants (
+  id INTEGER PRIMARY KEY,
+  player INTEGER NOT NULL REFERENCES players(id),
+  reality INTEGER NOT NULL REFERENCES realities(id),
+  claims INTEGER NOT NULL,
+  expires INTEGER NOT NULL,
+  granted_by INTEGER NOT NULL,
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
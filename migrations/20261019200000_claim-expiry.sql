@@ -0,0 +1,3 @@
+ALTER TABLE tracked_worlds ADD COLUMN claim_deadline INTEGER;
+ALTER TABLE tracked_worlds ADD COLUMN claim_idle_days INTEGER;
+ALTER TABLE claims ADD COLUMN expiry_warned_at INTEGER NOT NULL DEFAULT 0;
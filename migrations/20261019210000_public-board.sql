@@ -0,0 +1,26 @@
+ALTER TABLE claims ADD COLUMN public_kind INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE claims ADD COLUMN public_expires INTEGER;
+
+CREATE TABLE public_volunteers (
+  claim INTEGER NOT NULL REFERENCES claims(id) ON DELETE CASCADE,
+  player INTEGER NOT NULL REFERENCES players(id),
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  PRIMARY KEY (claim, player)
+) STRICT;
+
+CREATE TABLE public_filters (
+  id INTEGER PRIMARY KEY,
+  world TEXT,
+  game TEXT,
+  kind INTEGER
+) STRICT;
+
+DROP VIEW public_claims;
+
+CREATE VIEW public_claims (id, world, slot, games, owner, description, kind, expires)
+  AS SELECT claims.id, tracked_worlds.name, tracked_slots.name, tracked_slots.games, players.snowflake, public, public_kind, public_expires FROM claims
+    INNER JOIN tracked_slots ON claims.slot = tracked_slots.id
+    INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+    INNER JOIN players ON claims.player = players.id
+    WHERE public IS NOT NULL AND tracked_worlds.archived = 0 AND (public_expires IS NULL OR public_expires > strftime('%s', 'now'))
+    ORDER BY public_expires ASC, claims.id ASC;
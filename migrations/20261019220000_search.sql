@@ -0,0 +1,10 @@
+CREATE TABLE search_filters (
+  id INTEGER PRIMARY KEY,
+  game TEXT,
+  status INTEGER,
+  reality TEXT,
+  free INTEGER,
+  min_points INTEGER,
+  max_points INTEGER,
+  claimed INTEGER
+) STRICT;
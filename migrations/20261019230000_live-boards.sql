@@ -0,0 +1,7 @@
+CREATE TABLE live_boards (
+  id INTEGER PRIMARY KEY,
+  channel INTEGER NOT NULL UNIQUE,
+  message INTEGER NOT NULL,
+  created_by INTEGER NOT NULL,
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
@@ -0,0 +1,11 @@
+DROP VIEW sheets_push;
+
+CREATE VIEW sheets_push (world, reality, slot, games, status, checks, checks_total, free, points, last_activity, player, latest_update)
+  AS SELECT tracked_worlds.name, realities.name, tracked_slots.name, tracked_slots.games, status, checks, checks_total, free, tracked_slots.points, last_activity, players.name,
+  (SELECT description FROM updates WHERE updates.slot = tracked_slots.id ORDER BY timestamp DESC, updates.id DESC LIMIT 1)
+  FROM tracked_worlds
+  INNER JOIN tracked_slots ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  LEFT JOIN claims ON claims.slot = tracked_slots.id
+  LEFT JOIN players ON claims.player = players.id
+  WHERE tracked_worlds.archived = 0;
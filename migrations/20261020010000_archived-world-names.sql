@@ -0,0 +1,102 @@
+-- no-transaction
+-- Archived worlds keep their rows, so names and tracker urls only have to be unique among live worlds
+-- The unique constraints are part of the table, so it gets rebuilt with foreign keys off as sqlite recommends
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+DROP VIEW current_realities;
+DROP VIEW current_claims;
+DROP VIEW worlds_overview;
+DROP VIEW unclaimed_slots;
+DROP VIEW current_realities_claims;
+DROP VIEW public_claims;
+DROP VIEW sheets_push;
+
+CREATE TABLE tracked_worlds_new (
+  id INTEGER PRIMARY KEY,
+  tracker_id TEXT NOT NULL,
+  name TEXT NOT NULL,
+  last_scrape INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  done INTEGER NOT NULL DEFAULT 0,
+  reality INTEGER REFERENCES realities(id),
+  archived INTEGER NOT NULL DEFAULT 0,
+  archived_at INTEGER,
+  claim_deadline INTEGER,
+  claim_idle_days INTEGER
+) STRICT;
+
+INSERT INTO tracked_worlds_new (id, tracker_id, name, last_scrape, done, reality, archived, archived_at, claim_deadline, claim_idle_days)
+  SELECT id, tracker_id, name, last_scrape, done, reality, archived, archived_at, claim_deadline, claim_idle_days FROM tracked_worlds;
+
+DROP TABLE tracked_worlds;
+ALTER TABLE tracked_worlds_new RENAME TO tracked_worlds;
+
+CREATE UNIQUE INDEX tracked_worlds_live_name ON tracked_worlds (name) WHERE archived = 0;
+CREATE UNIQUE INDEX tracked_worlds_live_tracker ON tracked_worlds (tracker_id) WHERE archived = 0;
+
+CREATE VIEW current_realities (player, reality)
+  AS SELECT DISTINCT claims.player, tracked_worlds.reality FROM claims
+  LEFT JOIN tracked_slots ON claims.slot = tracked_slots.id
+  LEFT JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  WHERE (free = 0 OR free IS NULL) AND (status < 2 OR status IS NULL) AND tracked_worlds.reality IS NOT NULL AND archived = 0;
+
+CREATE VIEW current_claims (player, reality, claims)
+  AS SELECT claims.player, tracked_worlds.reality, COUNT(*) FROM claims
+  LEFT JOIN tracked_slots ON claims.slot = tracked_slots.id
+  LEFT JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  WHERE (free = 0 OR free IS NULL) AND (status < 2 OR status IS NULL) AND (archived = 0 OR archived IS NULL)
+  GROUP BY claims.player, tracked_worlds.reality;
+
+CREATE VIEW worlds_overview (id, name, reality, unclaimed, unstarted, in_progress, goal, all_checks, done) AS
+  SELECT tracked_worlds.id, tracked_worlds.name, realities.name,
+  COUNT(*) FILTER (WHERE claims.player IS NULL),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 0),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 1),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 2),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 3),
+  COUNT(*) FILTER (WHERE claims.player IS NOT NULL AND status = 4)
+  FROM tracked_worlds INNER JOIN tracked_slots ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN claims ON claims.slot = tracked_slots.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE tracked_worlds.archived = 0
+  GROUP BY tracked_worlds.name
+  ORDER BY tracked_worlds.id;
+
+CREATE VIEW unclaimed_slots (world, reality, slot, games, free)
+  AS SELECT tracked_worlds.name, realities.name, tracked_slots.name, tracked_slots.games, tracked_slots.free
+  FROM tracked_slots INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE tracked_slots.id NOT IN (SELECT slot FROM claims) AND tracked_worlds.archived = 0 ORDER BY tracked_worlds.id DESC;
+
+CREATE VIEW current_realities_claims (player, realities)
+  AS SELECT claims.player, COUNT(DISTINCT tracked_worlds.reality) FROM claims
+  LEFT JOIN tracked_slots ON claims.slot = tracked_slots.id
+  LEFT JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  WHERE (free = 0 OR free IS NULL) AND (status < 2 OR status IS NULL) AND tracked_worlds.reality IS NOT NULL AND external = 0 AND tracked_worlds.archived = 0
+  GROUP BY claims.player;
+
+CREATE VIEW public_claims (id, world, slot, games, owner, description, kind, expires)
+  AS SELECT claims.id, tracked_worlds.name, tracked_slots.name, tracked_slots.games, players.snowflake, public, public_kind, public_expires FROM claims
+    INNER JOIN tracked_slots ON claims.slot = tracked_slots.id
+    INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+    INNER JOIN players ON claims.player = players.id
+    WHERE public IS NOT NULL AND tracked_worlds.archived = 0 AND (public_expires IS NULL OR public_expires > strftime('%s', 'now'))
+    ORDER BY public_expires ASC, claims.id ASC;
+
+CREATE VIEW sheets_push (world, reality, slot, games, status, checks, checks_total, free, points, last_activity, player, latest_update)
+  AS SELECT tracked_worlds.name, realities.name, tracked_slots.name, tracked_slots.games, status, checks, checks_total, free, tracked_slots.points, last_activity, players.name,
+  (SELECT description FROM updates WHERE updates.slot = tracked_slots.id ORDER BY timestamp DESC, updates.id DESC LIMIT 1)
+  FROM tracked_worlds
+  INNER JOIN tracked_slots ON tracked_slots.world = tracked_worlds.id
+  LEFT JOIN realities ON realities.id = tracked_worlds.reality
+  LEFT JOIN claims ON claims.slot = tracked_slots.id
+  LEFT JOIN players ON claims.player = players.id
+  WHERE tracked_worlds.archived = 0;
+
+PRAGMA foreign_key_check;
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1 @@
+ALTER TABLE public_filters ADD COLUMN timestamp INTEGER NOT NULL DEFAULT 0;
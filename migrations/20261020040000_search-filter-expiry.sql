@@ -0,0 +1 @@
+ALTER TABLE search_filters ADD COLUMN timestamp INTEGER NOT NULL DEFAULT 0;
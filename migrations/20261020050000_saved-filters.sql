@@ -0,0 +1,11 @@
+-- Every paginated command saves its filters the same way, so they share one table keyed by command name
+DROP TABLE audit_filters;
+DROP TABLE public_filters;
+DROP TABLE search_filters;
+
+CREATE TABLE saved_filters (
+  id INTEGER PRIMARY KEY,
+  command TEXT NOT NULL,
+  filter TEXT NOT NULL,
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
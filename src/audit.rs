@@ -0,0 +1,62 @@
+use serenity::all::UserId;
+use sqlx::query;
+
+use crate::Bot;
+
+pub struct AuditEvent<'a> {
+    actor: UserId,
+    command: &'a str,
+    world: Option<&'a str>,
+    slot: Option<&'a str>,
+    target: Option<i64>,
+    outcome: String,
+}
+
+impl<'a> AuditEvent<'a> {
+    pub fn new(actor: UserId, command: &'a str, outcome: impl Into<String>) -> Self {
+        AuditEvent {
+            actor,
+            command,
+            world: None,
+            slot: None,
+            target: None,
+            outcome: outcome.into(),
+        }
+    }
+
+    pub fn world(mut self, world: &'a str) -> Self {
+        self.world = Some(world);
+        self
+    }
+
+    pub fn slot(mut self, slot: &'a str) -> Self {
+        self.slot = Some(slot);
+        self
+    }
+
+    pub fn target(mut self, target: impl Into<i64>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+}
+
+impl Bot {
+    pub async fn audit(&self, event: AuditEvent<'_>) {
+        let actor = i64::from(event.actor);
+
+        if let Err(err) = query!(
+            "INSERT INTO audit_events (actor, command, world, slot, target, outcome) VALUES (?, ?, ?, ?, ?, ?)",
+            actor,
+            event.command,
+            event.world,
+            event.slot,
+            event.target,
+            event.outcome
+        )
+        .execute(&self.db)
+        .await
+        {
+            println!("[FAILED AUDIT WRITE] {} /{} {:?} {:?}: {} ({err})", event.actor, event.command, event.world, event.slot, event.outcome);
+        }
+    }
+}
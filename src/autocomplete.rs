@@ -1,7 +1,7 @@
 use serenity::all::{AutocompleteChoice, CommandInteraction, Context, CreateAutocompleteResponse, CreateInteractionResponse};
 use sqlx::query;
 
-use crate::{Bot, Player};
+use crate::{commands::track_world::split_game_str, Bot, Player};
 
 pub trait Autocomplete {
     async fn no_autocomplete(&self, _ctx: &Context);
@@ -45,7 +45,23 @@ impl Bot {
 
     pub async fn autocomplete_realities(&self, ctx: Context, interaction: &CommandInteraction, partial: &str) {
         let filter = format!("%{partial}%");
-        let Ok(response) = query!("SELECT name FROM realities WHERE name LIKE ? ORDER BY name ASC LIMIT 25", filter).fetch_all(&self.db).await else {
+        let Ok(response) = query!("SELECT name FROM realities WHERE name LIKE ? AND archived = 0 ORDER BY name ASC LIMIT 25", filter)
+            .fetch_all(&self.db)
+            .await
+        else {
+            interaction.no_autocomplete(&ctx).await;
+            return;
+        };
+
+        interaction.autocomplete(&ctx, response.into_iter().map(|record| record.name)).await;
+    }
+
+    pub async fn autocomplete_all_realities(&self, ctx: Context, interaction: &CommandInteraction, partial: &str) {
+        let filter = format!("%{partial}%");
+        let Ok(response) = query!("SELECT name FROM realities WHERE name LIKE ? ORDER BY archived ASC, name ASC LIMIT 25", filter)
+            .fetch_all(&self.db)
+            .await
+        else {
             interaction.no_autocomplete(&ctx).await;
             return;
         };
@@ -55,7 +71,10 @@ impl Bot {
 
     pub async fn autocomplete_worlds(&self, ctx: Context, interaction: &CommandInteraction, partial: &str) {
         let filter = format!("%{partial}%");
-        let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE name LIKE ? ORDER BY name ASC LIMIT 25", filter).fetch_all(&self.db).await else {
+        let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE name LIKE ? AND archived = 0 ORDER BY name ASC LIMIT 25", filter)
+            .fetch_all(&self.db)
+            .await
+        else {
             interaction.no_autocomplete(&ctx).await;
             return;
         };
@@ -63,12 +82,69 @@ impl Bot {
         interaction.autocomplete(&ctx, response.into_iter().map(|record| record.name)).await;
     }
 
+    pub async fn autocomplete_archived_worlds(&self, ctx: Context, interaction: &CommandInteraction, partial: &str) {
+        let filter = format!("%{partial}%");
+        let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE name LIKE ? AND archived = 1 ORDER BY archived_at DESC LIMIT 25", filter)
+            .fetch_all(&self.db)
+            .await
+        else {
+            interaction.no_autocomplete(&ctx).await;
+            return;
+        };
+
+        interaction.autocomplete(&ctx, response.into_iter().map(|record| record.name)).await;
+    }
+
+    pub async fn autocomplete_audited_worlds(&self, ctx: Context, interaction: &CommandInteraction, partial: &str) {
+        let filter = format!("%{partial}%");
+        let Ok(response) = query!(
+            "SELECT world AS \"world!\" FROM audit_events WHERE world LIKE ? GROUP BY world ORDER BY MAX(timestamp) DESC LIMIT 25",
+            filter
+        )
+        .fetch_all(&self.db)
+        .await
+        else {
+            interaction.no_autocomplete(&ctx).await;
+            return;
+        };
+
+        interaction.autocomplete(&ctx, response.into_iter().map(|record| record.world)).await;
+    }
+
+    // Public games only come from slots currently on the public board
+    pub async fn autocomplete_games(&self, ctx: Context, interaction: &CommandInteraction, partial: &str, public_only: bool) {
+        let filter = format!("%{partial}%");
+        let Ok(response) = query!(
+            "SELECT games FROM tracked_slots INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+            WHERE games LIKE ?1 AND tracked_worlds.archived = 0 AND (?2 = 0 OR tracked_slots.id IN (SELECT claims.slot FROM claims INNER JOIN public_claims ON public_claims.id = claims.id))",
+            filter,
+            public_only
+        )
+        .fetch_all(&self.db)
+        .await
+        else {
+            interaction.no_autocomplete(&ctx).await;
+            return;
+        };
+
+        let partial = partial.to_lowercase();
+        let mut games: Vec<_> = response
+            .iter()
+            .flat_map(|record| split_game_str(&record.games).map(|(game, _)| game.to_owned()))
+            .filter(|game| game.to_lowercase().contains(&partial))
+            .collect();
+        games.sort();
+        games.dedup();
+
+        interaction.autocomplete(&ctx, games.into_iter().take(25)).await;
+    }
+
     pub async fn autocomplete_slots(&self, ctx: Context, interaction: &CommandInteraction, partial: &str, world: Option<&str>) {
         let filter = format!("%{partial}%");
 
         if let Some(world) = world {
             let Ok(response) = query!(
-                "SELECT name FROM tracked_slots WHERE world IN (SELECT id FROM tracked_worlds WHERE name = ?) AND name LIKE ? ORDER BY name ASC LIMIT 25",
+                "SELECT name FROM tracked_slots WHERE world IN (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0) AND name LIKE ? ORDER BY name ASC LIMIT 25",
                 world,
                 filter
             )
@@ -81,7 +157,13 @@ impl Bot {
 
             interaction.autocomplete(&ctx, response.into_iter().map(|record| record.name)).await;
         } else {
-            let Ok(response) = query!("SELECT name FROM tracked_slots WHERE name LIKE ? ORDER BY name ASC LIMIT 25", filter).fetch_all(&self.db).await else {
+            let Ok(response) = query!(
+                "SELECT name FROM tracked_slots WHERE world IN (SELECT id FROM tracked_worlds WHERE archived = 0) AND name LIKE ? ORDER BY name ASC LIMIT 25",
+                filter
+            )
+            .fetch_all(&self.db)
+            .await
+            else {
                 interaction.no_autocomplete(&ctx).await;
                 return;
             };
@@ -95,7 +177,7 @@ impl Bot {
 
         if let Some(world) = world {
             if let Ok(response) = query!(
-                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE claims.player = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ?) AND name LIKE ? ORDER BY name ASC LIMIT 25",
+                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE claims.player = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0) AND name LIKE ? ORDER BY name ASC LIMIT 25",
                 player.id,
                 world,
                 filter
@@ -107,7 +189,7 @@ impl Bot {
 
             let remaining = 25 - recommendations.len() as i64;
             if let Ok(response) = query!(
-                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE (claims.player != ? OR claims.player IS NULL) AND world IN (SELECT id FROM tracked_worlds WHERE name = ?) AND name LIKE ? ORDER BY name ASC LIMIT ?",
+                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE (claims.player != ? OR claims.player IS NULL) AND world IN (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0) AND name LIKE ? ORDER BY name ASC LIMIT ?",
                 player.id,
                 world,
                 filter,
@@ -120,7 +202,7 @@ impl Bot {
             }
         } else {
             if let Ok(response) = query!(
-                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE claims.player = ? AND name LIKE ? ORDER BY name ASC LIMIT 25",
+                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE claims.player = ? AND world IN (SELECT id FROM tracked_worlds WHERE archived = 0) AND name LIKE ? ORDER BY name ASC LIMIT 25",
                 player.id,
                 filter
             )
@@ -132,7 +214,7 @@ impl Bot {
 
             let remaining = 25 - recommendations.len() as i64;
             if let Ok(response) = query!(
-                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE (claims.player != ? OR claims.player IS NULL) AND name LIKE ? ORDER BY name ASC LIMIT ?",
+                "SELECT name FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id WHERE (claims.player != ? OR claims.player IS NULL) AND world IN (SELECT id FROM tracked_worlds WHERE archived = 0) AND name LIKE ? ORDER BY name ASC LIMIT ?",
                 player.id,
                 filter,
                 remaining
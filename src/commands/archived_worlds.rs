@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use serde_json::to_string;
+use serenity::all::{
+    AutocompleteOption, Colour, CommandInteraction, CommandType, ComponentInteraction, Context, CreateCommand, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, ModalInteraction, Timestamp,
+};
+use sqlx::query;
+
+use crate::{
+    autocomplete::Autocomplete,
+    commands::Command,
+    custom_id::Fields,
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
+    scrape::Status,
+    util::SimpleReply,
+    Bot,
+};
+
+pub struct World {
+    name: String,
+    reality: Option<String>,
+    archived_at: Option<i64>,
+    checks: i64,
+    checks_total: i64,
+    slots: Vec<SlotId>,
+}
+
+pub struct SlotId(i64);
+
+pub struct Slot {
+    name: String,
+    status: i64,
+    checks: i64,
+    checks_total: i64,
+    points: i64,
+    player: Option<i64>,
+    awarded_to: Option<i64>,
+}
+
+command_options! {
+    struct Options {
+        world: Option<String> = ("world", "Name of the world").set_autocomplete(true),
+    }
+}
+
+pub struct ArchivedWorldsCommand {}
+
+impl Command for ArchivedWorldsCommand {
+    const NAME: &'static str = "archived-worlds";
+
+    fn register() -> CreateCommand {
+        Options::add_options(CreateCommand::new(Self::NAME).description("Browse finished worlds and their results").kind(CommandType::ChatInput))
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let Some(Options { world: world_name }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let worlds = Self::get_containers(bot, (), View::default()).await;
+
+        if worlds.is_empty() {
+            command.simple_reply(&ctx, "There are no archived worlds").await;
+            return;
+        }
+
+        let start_page = if let Some(world_name) = world_name {
+            let Some(index) = worlds.iter().position(|world| world.name == world_name) else {
+                command.simple_reply(&ctx, "World not found").await;
+                return;
+            };
+
+            worlds[..index].iter().map(|world| world.slots.len().div_ceil(Self::PAGE_SIZE)).sum()
+        } else {
+            0
+        };
+
+        let Some(response) = Self::page(bot, (), View::default(), start_page).await else {
+            command.simple_reply(&ctx, "There are no archived worlds").await;
+            return;
+        };
+
+        let _ = command.create_response(&ctx.http, CreateInteractionResponse::Message(response.into())).await;
+    }
+
+    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
+        match interaction.data.autocomplete() {
+            Some(AutocompleteOption { name: "world", value, .. }) => bot.autocomplete_archived_worlds(ctx, &interaction, value).await,
+            Some(_) | None => {
+                interaction.no_autocomplete(&ctx).await;
+            }
+        }
+    }
+}
+
+impl Paginate<World, SlotId, Slot> for ArchivedWorldsCommand {
+    const PAGE_SIZE: usize = 10;
+
+    async fn get_containers(bot: &Bot, _: (), _view: View) -> Vec<World> {
+        let Ok(response) = query!(
+            "SELECT tracked_worlds.id AS world_id, tracked_worlds.name AS world_name, realities.name AS reality, archived_at, tracked_slots.id AS slot_id, checks, checks_total
+            FROM tracked_worlds INNER JOIN tracked_slots ON tracked_worlds.id = tracked_slots.world LEFT JOIN realities ON tracked_worlds.reality = realities.id
+            WHERE tracked_worlds.archived = 1 ORDER BY archived_at DESC, tracked_worlds.id DESC, tracked_slots.name ASC"
+        )
+        .fetch_all(&bot.db)
+        .await
+        else {
+            return vec![];
+        };
+
+        let mut worlds: Vec<(i64, World)> = vec![];
+
+        for record in response {
+            if let Some((_, world)) = worlds.last_mut().filter(|(id, _)| *id == record.world_id) {
+                world.checks += record.checks;
+                world.checks_total += record.checks_total;
+                world.slots.push(SlotId(record.slot_id));
+            } else {
+                worlds.push((
+                    record.world_id,
+                    World {
+                        name: record.world_name,
+                        reality: record.reality,
+                        archived_at: record.archived_at,
+                        checks: record.checks,
+                        checks_total: record.checks_total,
+                        slots: vec![SlotId(record.slot_id)],
+                    },
+                ));
+            }
+        }
+
+        worlds.into_iter().map(|(_, world)| world).collect()
+    }
+
+    async fn details_for_page(bot: &Bot, items: &[SlotId], _extra: ()) -> Vec<Slot> {
+        slot_details(bot, &items.iter().map(|slot| slot.0).collect::<Vec<_>>()).await
+    }
+}
+
+impl PageContainer<SlotId, Slot> for World {
+    fn items(&self) -> &[SlotId] {
+        &self.slots
+    }
+
+    fn page_setup(&self) -> CreateEmbed {
+        let embed = CreateEmbed::new()
+            .title(format!("{}{}", self.name, if let Some(reality) = &self.reality { format!(" [{reality}]") } else { String::new() }))
+            .colour(Colour::DARK_PURPLE)
+            .footer(CreateEmbedFooter::new(format!("Total checks: {}/{}", self.checks, self.checks_total)));
+
+        if let Some(archived_at) = self.archived_at {
+            embed.timestamp(Timestamp::from_unix_timestamp(archived_at).unwrap_or_default())
+        } else {
+            embed
+        }
+    }
+}
+
+impl PageItem<Slot> for SlotId {
+    async fn details(&self, bot: &Bot, _extra: ()) -> Option<Slot> {
+        slot_details(bot, &[self.0]).await.pop()
+    }
+}
+
+async fn slot_details(bot: &Bot, slot_ids: &[i64]) -> Vec<Slot> {
+    let Ok(ids) = to_string(slot_ids) else {
+        return vec![];
+    };
+
+    let Ok(response) = query!(
+        "SELECT tracked_slots.id AS \"id!\", tracked_slots.name, status, checks, checks_total, tracked_slots.points, claimant.snowflake AS \"player?\", recipient.snowflake AS \"awarded_to?\" FROM tracked_slots
+        LEFT JOIN claims ON claims.slot = tracked_slots.id
+        LEFT JOIN players AS claimant ON claimant.id = claims.player
+        LEFT JOIN players AS recipient ON recipient.id = tracked_slots.awarded_to
+        WHERE tracked_slots.id IN (SELECT value FROM json_each(?))",
+        ids
+    )
+    .fetch_all(&bot.db)
+    .await
+    else {
+        return vec![];
+    };
+
+    let mut slots: HashMap<i64, Slot> = response
+        .into_iter()
+        .map(|record| {
+            (
+                record.id,
+                Slot {
+                    name: record.name,
+                    status: record.status,
+                    checks: record.checks,
+                    checks_total: record.checks_total,
+                    points: record.points,
+                    player: record.player,
+                    awarded_to: record.awarded_to,
+                },
+            )
+        })
+        .collect();
+
+    slot_ids.iter().filter_map(|id| slots.remove(id)).collect()
+}
+
+impl PageDetails for Slot {
+    fn field(&self) -> (String, String, bool) {
+        (
+            format!("{} ({}/{})", self.name, self.checks, self.checks_total),
+            format!(
+                "{}\n**Status**: {}\n**Points**: {}",
+                if let Some(player) = self.player {
+                    format!("**Claimed by**: <@{player}>")
+                } else {
+                    String::from("*Unclaimed*")
+                },
+                Status::from_i64(self.status).map(|status| status.as_str()).unwrap_or("Unknown"),
+                match (self.awarded_to, self.player) {
+                    (Some(awarded_to), Some(player)) if awarded_to != player => format!("{} to <@{awarded_to}>", self.points),
+                    (Some(_), _) => self.points.to_string(),
+                    (None, _) => String::from("*None awarded*"),
+                }
+            ),
+            false,
+        )
+    }
+}
+
+impl ArchivedWorldsCommand {
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, fields: Fields<'_>) {
+        if !Self::try_handle_interaction(bot, &ctx, interaction, fields, ()).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, fields: Fields<'_>) {
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, ()).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    AutocompleteOption, Colour, CommandInteraction, CommandType, ComponentInteraction, Context, CreateCommand, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, ModalInteraction, User,
+};
+use sqlx::query;
+
+use crate::{
+    autocomplete::Autocomplete,
+    commands::Command,
+    custom_id::{CustomId, Fields},
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
+    util::SimpleReply,
+    Bot,
+};
+
+#[derive(Serialize, Deserialize)]
+struct Filter {
+    player: Option<i64>,
+    world: Option<String>,
+    after: Option<i64>,
+    before: Option<i64>,
+}
+
+pub struct AuditLog {
+    filter: String,
+    events: Vec<Event>,
+}
+
+#[derive(Clone)]
+pub struct Event {
+    id: i64,
+    actor: i64,
+    command: String,
+    world: Option<String>,
+    slot: Option<String>,
+    target: Option<i64>,
+    outcome: String,
+    timestamp: i64,
+}
+
+command_options! {
+    struct Options {
+        player: Option<User> = ("player", "Only show actions taken by or affecting this player"),
+        world: Option<String> = ("world", "Only show actions in this world").set_autocomplete(true),
+        after: Option<i64> = ("after", "Only show actions after this unix timestamp"),
+        before: Option<i64> = ("before", "Only show actions before this unix timestamp"),
+    }
+}
+
+pub struct AuditCommand {}
+
+impl Command for AuditCommand {
+    const NAME: &'static str = "audit";
+
+    fn register() -> CreateCommand {
+        Options::add_options(CreateCommand::new(Self::NAME).description("View the log of actions taken through the bot").kind(CommandType::ChatInput))
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        if !bot.privileged.contains(&command.user.id) {
+            command.simple_reply(&ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(Options { player, world, after, before }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let player = player.map(|player| i64::from(player.id));
+
+        let Some(filter) = bot.save_filter(Self::NAME, &Filter { player, world, after, before }).await else {
+            command.simple_reply(&ctx, "Failed to save audit filter").await;
+            return;
+        };
+
+        let Some(response) = Self::first_page(bot, filter).await else {
+            command.simple_reply(&ctx, "No actions match this filter").await;
+            return;
+        };
+
+        let _ = command.create_response(&ctx.http, CreateInteractionResponse::Message(response.into())).await;
+    }
+
+    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
+        match interaction.data.autocomplete() {
+            Some(AutocompleteOption { name: "world", value, .. }) => bot.autocomplete_audited_worlds(ctx, &interaction, value).await,
+            Some(_) | None => {
+                interaction.no_autocomplete(&ctx).await;
+            }
+        }
+    }
+}
+
+impl Paginate<AuditLog, Event, Event, i64> for AuditCommand {
+    const PAGE_SIZE: usize = 10;
+
+    async fn get_containers(bot: &Bot, filter_id: i64, _view: View) -> Vec<AuditLog> {
+        let Some(filter) = bot.saved_filter::<Filter>(Self::NAME, filter_id).await else {
+            return vec![];
+        };
+
+        let Ok(response) = query!(
+            "SELECT id, actor, command, world, slot, target, outcome, timestamp FROM audit_events
+            WHERE (?1 IS NULL OR actor = ?1 OR target = ?1) AND (?2 IS NULL OR world = ?2) AND (?3 IS NULL OR timestamp >= ?3) AND (?4 IS NULL OR timestamp <= ?4)
+            ORDER BY id DESC",
+            filter.player,
+            filter.world,
+            filter.after,
+            filter.before
+        )
+        .fetch_all(&bot.db)
+        .await
+        else {
+            return vec![];
+        };
+
+        if response.is_empty() {
+            return vec![];
+        }
+
+        let mut description = vec![];
+        if let Some(player) = filter.player {
+            description.push(format!("Player: <@{player}>"));
+        }
+        if let Some(world) = filter.world {
+            description.push(format!("World: {world}"));
+        }
+        if let Some(after) = filter.after {
+            description.push(format!("After: <t:{after}:f>"));
+        }
+        if let Some(before) = filter.before {
+            description.push(format!("Before: <t:{before}:f>"));
+        }
+
+        vec![AuditLog {
+            filter: description.join("\n"),
+            events: response
+                .into_iter()
+                .map(|record| Event {
+                    id: record.id,
+                    actor: record.actor,
+                    command: record.command,
+                    world: record.world,
+                    slot: record.slot,
+                    target: record.target,
+                    outcome: record.outcome,
+                    timestamp: record.timestamp,
+                })
+                .collect(),
+        }]
+    }
+
+    fn id_prefix(filter_id: i64) -> CustomId {
+        CustomId::new(Self::NAME).push(filter_id)
+    }
+}
+
+impl PageContainer<Event, Event, i64> for AuditLog {
+    fn items(&self) -> &[Event] {
+        &self.events
+    }
+
+    fn page_setup(&self) -> CreateEmbed {
+        let embed = CreateEmbed::new().title("Audit log").colour(Colour::DARK_PURPLE);
+
+        if self.filter.is_empty() { embed } else { embed.description(&self.filter) }.footer(CreateEmbedFooter::new(format!("{} actions", self.events.len())))
+    }
+}
+
+impl PageItem<Event, i64> for Event {
+    async fn details(&self, _bot: &Bot, _extra: i64) -> Option<Event> {
+        Some(self.clone())
+    }
+}
+
+impl PageDetails for Event {
+    fn field(&self) -> (String, String, bool) {
+        let mut lines = vec![format!("<t:{}:f> by <@{}>", self.timestamp, self.actor)];
+
+        match (&self.world, &self.slot) {
+            (Some(world), Some(slot)) => lines.push(format!("**Slot**: {slot} in {world}")),
+            (Some(world), None) => lines.push(format!("**World**: {world}")),
+            (None, Some(slot)) => lines.push(format!("**Slot**: {slot}")),
+            (None, None) => (),
+        }
+
+        if let Some(target) = self.target {
+            lines.push(format!("**Player**: <@{target}>"));
+        }
+
+        lines.push(format!("**Outcome**: {}", self.outcome));
+
+        (format!("#{} /{}", self.id, self.command), lines.join("\n"), false)
+    }
+}
+
+impl AuditCommand {
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, mut fields: Fields<'_>) {
+        let Some(filter_id) = fields.next() else {
+            interaction.simple_reply(&ctx, "Malformed audit filter").await;
+            return;
+        };
+
+        if !Self::try_handle_interaction(bot, &ctx, interaction, fields, filter_id).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, mut fields: Fields<'_>) {
+        let Some(filter_id) = fields.next() else {
+            interaction.simple_reply(&ctx, "Malformed audit filter").await;
+            return;
+        };
+
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, filter_id).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+}
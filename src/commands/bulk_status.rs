@@ -1,11 +1,38 @@
 use std::iter::once;
 
-use serenity::all::{
-    AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, CreateMessage, EditInteractionResponse, ResolvedOption, ResolvedValue,
-};
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, CreateMessage, EditInteractionResponse, ResolvedOption, ResolvedValue};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        world1: String = ("world1", "Name of the world").set_autocomplete(true),
+        slot1: String = ("slot1", "Name of the slot").set_autocomplete(true),
+        description1: String = ("description1", "Status description").set_autocomplete(true),
+        world2: Option<String> = ("world2", "Name of the world").set_autocomplete(true),
+        slot2: Option<String> = ("slot2", "Name of the slot").set_autocomplete(true),
+        description2: Option<String> = ("description2", "Status description").set_autocomplete(true),
+        world3: Option<String> = ("world3", "Name of the world").set_autocomplete(true),
+        slot3: Option<String> = ("slot3", "Name of the slot").set_autocomplete(true),
+        description3: Option<String> = ("description3", "Status description").set_autocomplete(true),
+        world4: Option<String> = ("world4", "Name of the world").set_autocomplete(true),
+        slot4: Option<String> = ("slot4", "Name of the slot").set_autocomplete(true),
+        description4: Option<String> = ("description4", "Status description").set_autocomplete(true),
+        world5: Option<String> = ("world5", "Name of the world").set_autocomplete(true),
+        slot5: Option<String> = ("slot5", "Name of the slot").set_autocomplete(true),
+        description5: Option<String> = ("description5", "Status description").set_autocomplete(true),
+        world6: Option<String> = ("world6", "Name of the world").set_autocomplete(true),
+        slot6: Option<String> = ("slot6", "Name of the slot").set_autocomplete(true),
+        description6: Option<String> = ("description6", "Status description").set_autocomplete(true),
+        world7: Option<String> = ("world7", "Name of the world").set_autocomplete(true),
+        slot7: Option<String> = ("slot7", "Name of the slot").set_autocomplete(true),
+        description7: Option<String> = ("description7", "Status description").set_autocomplete(true),
+        world8: Option<String> = ("world8", "Name of the world").set_autocomplete(true),
+        slot8: Option<String> = ("slot8", "Name of the slot").set_autocomplete(true),
+        description8: Option<String> = ("description8", "Status description").set_autocomplete(true),
+    }
+}
 
 pub struct BulkStatusCommand {}
 
@@ -13,121 +40,44 @@ impl Command for BulkStatusCommand {
     const NAME: &'static str = "bulk-status";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Report the status of multiple slots")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world1", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot1", "Name of the slot").required(true).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description1", "Status description")
-                    .required(true)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "world2", "Name of the world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot2", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description2", "Status description")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "world3", "Name of the world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot3", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description3", "Status description")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "world4", "Name of the world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot4", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description4", "Status description")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "world5", "Name of the world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot5", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description5", "Status description")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "world6", "Name of the world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot6", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description6", "Status description")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "world7", "Name of the world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot7", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description7", "Status description")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "world8", "Name of the world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot8", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description8", "Status description")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
+        Options::add_options(CreateCommand::new(Self::NAME).description("Report the status of multiple slots").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut worlds: [Option<&str>; 8] = [None; 8];
-        let mut slots: [Option<&str>; 8] = [None; 8];
-        let mut descriptions: [Option<&str>; 8] = [None; 8];
-        let mut active_world = None;
+        let Some(Options {
+            world1,
+            slot1,
+            description1,
+            world2,
+            slot2,
+            description2,
+            world3,
+            slot3,
+            description3,
+            world4,
+            slot4,
+            description4,
+            world5,
+            slot5,
+            description5,
+            world6,
+            slot6,
+            description6,
+            world7,
+            slot7,
+            description7,
+            world8,
+            slot8,
+            description8,
+        }) = Options::parse(&ctx, &command).await
+        else {
+            return;
+        };
 
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name.split_at(option_name.len() - 1), value) {
-                (("world", i), ResolvedValue::String(value)) => {
-                    if let Ok(i) = i.parse::<usize>() {
-                        worlds[i] = Some(value);
-                    }
-                }
-                (("slot", i), ResolvedValue::String(value)) => {
-                    if let Ok(i) = i.parse::<usize>() {
-                        slots[i] = Some(value);
-                    }
-                }
-                (("description", i), ResolvedValue::String(value)) => {
-                    if let Ok(i) = i.parse::<usize>() {
-                        descriptions[i] = Some(value);
-                    }
-                }
-                _ => (),
-            }
-        }
+        let worlds = [Some(world1), world2, world3, world4, world5, world6, world7, world8];
+        let slots = [Some(slot1), slot2, slot3, slot4, slot5, slot6, slot7, slot8];
+        let descriptions = [Some(description1), description2, description3, description4, description5, description6, description7, description8];
+        let mut active_world = None;
 
         let Some(player) = bot.get_player(i64::from(command.user.id), &command.user.name).await else {
             command.simple_reply(&ctx, "Failed to get user").await;
@@ -137,14 +87,18 @@ impl Command for BulkStatusCommand {
         let _ = command.defer_ephemeral(&ctx.http).await;
         let mut status_channel_msg = vec![];
 
-        for i in 0..8 {
-            if worlds[i].is_some() {
-                active_world = worlds[i];
+        for ((world, slot), description) in worlds.iter().zip(&slots).zip(&descriptions) {
+            if world.is_some() {
+                active_world = world.as_deref();
             }
-            if let (Some(world), Some(slot), Some(description)) = (active_world, slots[i], descriptions[i]) {
-                let slot_id = if let Ok(response) = query!("SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ?)", slot, world)
-                    .fetch_one(&bot.db)
-                    .await
+            if let (Some(world), Some(slot), Some(description)) = (active_world, slot.as_deref(), description.as_deref()) {
+                let slot_id = if let Ok(response) = query!(
+                    "SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0)",
+                    slot,
+                    world
+                )
+                .fetch_one(&bot.db)
+                .await
                 {
                     response.id
                 } else {
@@ -159,8 +113,10 @@ impl Command for BulkStatusCommand {
                     .await
                     .is_ok()
                 {
+                    bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Posted update").world(world).slot(slot)).await;
                     status_channel_msg.push(format!("[{}] [{world}] [{slot}] {description}", command.user.display_name()));
                 } else {
+                    bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Failed to update status").world(world).slot(slot)).await;
                     let _ = command
                         .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Failed to update status for slot {slot} in {world}")))
                         .await;
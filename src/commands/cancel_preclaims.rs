@@ -1,7 +1,21 @@
-use serenity::all::{AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, ResolvedOption, ResolvedValue};
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{
+    audit::AuditEvent,
+    autocomplete::Autocomplete,
+    commands::Command,
+    options::command_options,
+    undo::{PreclaimWorld, UndoAction},
+    util::SimpleReply,
+    Bot,
+};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+    }
+}
 
 pub struct CancelPreclaimsCommand {}
 
@@ -9,10 +23,7 @@ impl Command for CancelPreclaimsCommand {
     const NAME: &'static str = "cancel-preclaims";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Cancels current preclaims for a world")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Cancels current preclaims for a world").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -23,24 +34,46 @@ impl Command for CancelPreclaimsCommand {
             return;
         }
 
-        let mut world = "";
+        let Some(Options { world }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
 
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            if let ("world", ResolvedValue::String(value)) = (option_name, value) {
-                world = value
-            }
+        let Ok(mut transaction) = bot.db.begin().await else {
+            command.simple_reply(&ctx, "Failed to create transaction").await;
+            return;
+        };
+
+        let Ok(Some(preclaim_world)) = PreclaimWorld::fetch(&mut transaction, &world).await else {
+            let _ = transaction.rollback().await;
+            command.simple_reply(&ctx, "Failed to find world").await;
+            return;
+        };
+
+        if let Err(err) = query!("DELETE FROM worlds WHERE name = ?", world).execute(&mut *transaction).await {
+            println!("Failed to cancel preclaims: {err}");
+            let _ = transaction.rollback().await;
+            command.simple_reply(&ctx, "Failed to cancel preclaims").await;
+            return;
         }
 
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
+        if let Err(err) = (UndoAction::CancelPreclaims { world: preclaim_world })
+            .record(&mut transaction, i64::from(user), Self::NAME, &format!("Cancelled preclaims for {world}"))
+            .await
+        {
+            println!("Failed to record undo entry: {err}");
+            let _ = transaction.rollback().await;
+            command.simple_reply(&ctx, "Failed to record undo entry. Aborting").await;
             return;
         }
 
-        if query!("DELETE FROM worlds WHERE name = ?", world).execute(&bot.db).await.is_ok() {
-            command.simple_reply(&ctx, format!("Cancelled preclaims for {world}")).await;
-        } else {
-            command.simple_reply(&ctx, "Failed to cancel preclaims").await;
+        if let Err(err) = transaction.commit().await {
+            println!("Failed to commit transaction: {err}");
+            command.simple_reply(&ctx, "Failed to commit transaction. Aborting").await;
+            return;
         }
+
+        bot.audit(AuditEvent::new(user, Self::NAME, "Cancelled preclaims").world(&world)).await;
+        command.simple_reply(&ctx, format!("Cancelled preclaims for {world}")).await;
     }
 
     async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
@@ -1,7 +1,14 @@
-use serenity::all::{AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, EditInteractionResponse, ResolvedOption, ResolvedValue};
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, EditInteractionResponse, ResolvedOption, ResolvedValue, User};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, commands::Command, discord::Discord, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+        slot: String = ("slot", "Name of the slot").set_autocomplete(true),
+    }
+}
 
 pub struct ClaimCommand {}
 
@@ -9,105 +16,109 @@ impl Command for ClaimCommand {
     const NAME: &'static str = "claim";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Claim a slot in a world")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot", "Name of the slot").required(true).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Claim a slot in a world").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let user = i64::from(command.user.id);
-        let mut world = "";
-        let mut slot = "";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("slot", ResolvedValue::String(value)) => slot = value,
-                _ => (),
-            }
-        }
+        Self::run(bot, &ctx, &command).await;
+    }
 
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
-            return;
-        }
+    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
+        match interaction.data.autocomplete() {
+            Some(AutocompleteOption { name: "world", value, .. }) => bot.autocomplete_worlds(ctx, &interaction, value).await,
+            Some(AutocompleteOption { name: "slot", value, .. }) => {
+                let mut world = None;
+                for ResolvedOption { name: option_name, value, .. } in interaction.data.options() {
+                    if let ("world", ResolvedValue::String(value)) = (option_name, value) {
+                        world = Some(value)
+                    }
+                }
 
-        if slot.is_empty() {
-            command.simple_reply(&ctx, "A slot is required").await;
-            return;
+                bot.autocomplete_slots(ctx, &interaction, value, world).await;
+            }
+            Some(_) | None => {
+                interaction.no_autocomplete(&ctx).await;
+            }
         }
+    }
+}
 
-        let Some(player) = bot.get_player(user, &command.user.name).await else {
-            command.simple_reply(&ctx, "Failed to get user").await;
+impl ClaimCommand {
+    pub async fn run(bot: &Bot, ctx: &impl Discord, command: &CommandInteraction) {
+        let Some(Options { world, slot }) = Options::parse(ctx, command).await else {
             return;
         };
 
-        let (slot_id, free) = if let Ok(response) = query!(
-            "SELECT id, free FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ?) LIMIT 1",
+        let Ok(Some(response)) = query!(
+            "SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0) LIMIT 1",
             slot,
             world
         )
-        .fetch_one(&bot.db)
+        .fetch_optional(&bot.db)
         .await
-        {
-            (response.id, response.free > 0)
-        } else {
-            command.simple_reply(&ctx, "Failed to get slot").await;
+        else {
+            command.simple_reply(ctx, "Failed to get slot").await;
             return;
         };
 
-        if let Ok(response) = query!("SELECT id FROM claims WHERE slot = ? LIMIT 1", slot_id).fetch_optional(&bot.db).await {
-            if response.is_some() {
-                command.simple_reply(&ctx, "Slot is already claimed").await;
-                return;
-            }
-        } else {
-            command.simple_reply(&ctx, "Failed to get claim status for slot").await;
-            return;
+        let _ = ctx.defer_ephemeral(command).await;
+
+        let content = match bot.claim(&command.user, response.id).await {
+            Ok(content) | Err(content) => content,
+        };
+
+        let _ = ctx.edit_response(&command.token, EditInteractionResponse::new().content(content)).await;
+    }
+}
+
+impl Bot {
+    pub async fn claim(&self, user: &User, slot_id: i64) -> Result<String, String> {
+        let Some(player) = self.get_player(i64::from(user.id), &user.name).await else {
+            return Err(String::from("Failed to get user"));
+        };
+
+        let Ok(Some(response)) = query!(
+            "SELECT tracked_slots.name AS slot, tracked_worlds.name AS world, free FROM tracked_slots INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+            WHERE tracked_slots.id = ? AND tracked_worlds.archived = 0 LIMIT 1",
+            slot_id
+        )
+        .fetch_optional(&self.db)
+        .await
+        else {
+            return Err(String::from("Failed to get slot"));
+        };
+
+        let (world, slot) = (response.world, response.slot);
+
+        match query!("SELECT id FROM claims WHERE slot = ? LIMIT 1", slot_id).fetch_optional(&self.db).await {
+            Ok(Some(_)) => return Err(String::from("Slot is already claimed")),
+            Ok(None) => (),
+            Err(_) => return Err(String::from("Failed to get claim status for slot")),
         }
 
-        if !free {
-            if let Err(reason) = bot.can_claim_slot(player.id, slot_id).await {
-                command.simple_reply(&ctx, reason).await;
-                return;
+        if response.free == 0 {
+            if let Err(reason) = self.can_claim_slot(player.id, slot_id).await {
+                self.audit(AuditEvent::new(user.id, ClaimCommand::NAME, format!("Rejected: {reason}")).world(&world).slot(&slot)).await;
+                return Err(reason);
             }
         }
 
-        let _ = command.defer_ephemeral(&ctx.http).await;
-
-        if query!("INSERT INTO claims (slot, player) VALUES (?, ?)", slot_id, player.id).execute(&bot.db).await.is_ok() {
-            if query!("DELETE FROM preclaims WHERE player = ? AND status = 0", player.id).execute(&bot.db).await.is_err() {
-                println!("Failed to remove old preclaims from {}", player.id)
-            }
+        if query!("INSERT INTO claims (slot, player, timestamp) VALUES (?, ?, strftime('%s', 'now'))", slot_id, player.id)
+            .execute(&self.db)
+            .await
+            .is_err()
+        {
+            self.audit(AuditEvent::new(user.id, ClaimCommand::NAME, "Failed to claim slot").world(&world).slot(&slot)).await;
+            return Err(String::from("Failed to claim slot"));
+        }
 
-            bot.log(&format!("Slot {slot} in {world} was claimed by {}", player.name));
-            bot.push_needed().await;
-            let _ = command
-                .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Successfully claimed {slot} in {world}")))
-                .await;
-        } else {
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to claim slot")).await;
+        if query!("DELETE FROM preclaims WHERE player = ? AND status = 0", player.id).execute(&self.db).await.is_err() {
+            println!("Failed to remove old preclaims from {}", player.id)
         }
-    }
 
-    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
-        match interaction.data.autocomplete() {
-            Some(AutocompleteOption { name: "world", value, .. }) => bot.autocomplete_worlds(ctx, &interaction, value).await,
-            Some(AutocompleteOption { name: "slot", value, .. }) => {
-                let mut world = None;
-                for ResolvedOption { name: option_name, value, .. } in interaction.data.options() {
-                    if let ("world", ResolvedValue::String(value)) = (option_name, value) {
-                        world = Some(value)
-                    }
-                }
+        self.audit(AuditEvent::new(user.id, ClaimCommand::NAME, "Claimed").world(&world).slot(&slot)).await;
+        self.push_needed().await;
 
-                bot.autocomplete_slots(ctx, &interaction, value, world).await;
-            }
-            Some(_) | None => {
-                interaction.no_autocomplete(&ctx).await;
-            }
-        }
+        Ok(format!("Successfully claimed {slot} in {world}"))
     }
 }
@@ -1,7 +1,13 @@
-use serenity::all::{CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, CreateEmbed, EditInteractionResponse, ResolvedOption, ResolvedValue};
+use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand, CreateEmbed, EditInteractionResponse, User};
 use sqlx::query;
 
-use crate::{commands::Command, scrape::Status, util::SimpleReply, Bot};
+use crate::{commands::Command, options::command_options, scrape::Status, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        player: Option<User> = ("player", "Player to view claimed slots for, defaults to yourself"),
+    }
+}
 
 pub struct ClaimedCommand {}
 
@@ -9,20 +15,15 @@ impl Command for ClaimedCommand {
     const NAME: &'static str = "claimed";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("View claimed slots")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::User, "player", "Player to view claimed slots for, defaults to yourself").required(false))
+        Options::add_options(CreateCommand::new(Self::NAME).description("View claimed slots").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut user = i64::from(command.user.id);
+        let Some(Options { player }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
 
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            if let ("player", ResolvedValue::User(value, _)) = (option_name, value) {
-                user = i64::from(value.id);
-            }
-        }
+        let user = i64::from(player.map_or(command.user.id, |player| player.id));
 
         let player = if let Some(player) = bot.get_player(user, &command.user.name).await {
             player
@@ -33,10 +34,22 @@ impl Command for ClaimedCommand {
 
         let _ = command.defer_ephemeral(&ctx.http).await;
 
-        if let Ok(response) = query!("SELECT tracked_worlds.name AS world, tracked_slots.id AS slot_id, tracked_slots.name AS slot, status, free FROM claims INNER JOIN tracked_slots ON claims.slot = tracked_slots.id INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id WHERE player = ? LIMIT 20", player.id).fetch_all(&bot.db).await {
-            let fields = if response.is_empty() {
-                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Player has no claimed slots")).await;
-                return;
+        if let Ok(response) = query!("SELECT tracked_worlds.name AS world, tracked_slots.id AS slot_id, tracked_slots.name AS slot, status, free FROM claims INNER JOIN tracked_slots ON claims.slot = tracked_slots.id INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id WHERE player = ? AND archived = 0 LIMIT 20", player.id).fetch_all(&bot.db).await {
+            let grants = query!(
+                "SELECT realities.name, claims, expires FROM claim_grants INNER JOIN realities ON realities.id = claim_grants.reality WHERE player = ? AND expires > strftime('%s', 'now') ORDER BY expires ASC",
+                player.id
+            )
+            .fetch_all(&bot.db)
+            .await
+            .unwrap_or_default();
+
+            let mut fields = if response.is_empty() {
+                if grants.is_empty() {
+                    let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Player has no claimed slots")).await;
+                    return;
+                }
+
+                vec![]
             } else {
                 let mut game_fields = vec![];
                 for record in response {
@@ -71,6 +84,18 @@ impl Command for ClaimedCommand {
                 game_fields
             };
 
+            if !grants.is_empty() {
+                fields.push((
+                    String::from("Bonus claims"),
+                    grants
+                        .into_iter()
+                        .map(|record| format!("+{} in {} until <t:{}:f>", record.claims, record.name, record.expires))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    false,
+                ));
+            }
+
             let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().embed(CreateEmbed::new().fields(fields))).await;
         } else {
             let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get unclaimed slots")).await;
@@ -1,7 +1,14 @@
-use serenity::all::{AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, ResolvedOption, ResolvedValue};
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, ResolvedOption, ResolvedValue};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{autocomplete::Autocomplete, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+        slot: String = ("slot", "Name of the slot").set_autocomplete(true),
+    }
+}
 
 pub struct FindCommand {}
 
@@ -9,37 +16,16 @@ impl Command for FindCommand {
     const NAME: &'static str = "find";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Find the owner of a slot")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot", "Name of the slot").required(true).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Find the owner of a slot").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut world = "";
-        let mut slot = "";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("slot", ResolvedValue::String(value)) => slot = value,
-                _ => (),
-            }
-        }
-
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
-            return;
-        }
-
-        if slot.is_empty() {
-            command.simple_reply(&ctx, "A slot is required").await;
+        let Some(Options { world, slot }) = Options::parse(&ctx, &command).await else {
             return;
-        }
+        };
 
         let slot_id = if let Ok(response) = query!(
-            "SELECT id FROM tracked_slots WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ?) LIMIT 1",
+            "SELECT id FROM tracked_slots WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0) LIMIT 1",
             slot,
             world
         )
@@ -1,10 +1,24 @@
-use serenity::all::{
-    AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, CreateEmbed, CreateMessage, EditInteractionResponse, ResolvedOption,
-    ResolvedValue,
-};
+use serenity::all::{AutocompleteOption, ChannelId, CommandInteraction, CommandType, Context, CreateCommand, CreateEmbed, CreateMessage, EditInteractionResponse};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{
+    audit::AuditEvent,
+    autocomplete::Autocomplete,
+    channels::STATUS_CHANNEL,
+    commands::Command,
+    discord::Discord,
+    options::command_options,
+    scrape::Status,
+    undo::{Award, PreclaimWorld, UndoAction},
+    util::SimpleReply,
+    Bot,
+};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+    }
+}
 
 pub struct FinishWorldCommand {}
 
@@ -12,141 +26,213 @@ impl Command for FinishWorldCommand {
     const NAME: &'static str = "finish-world";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Awards points for a world and deletes it and all claims in it")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Awards points for a world and archives it").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut world = "";
+        Self::run(bot, &ctx, &command).await;
+    }
 
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            if let ("world", ResolvedValue::String(value)) = (option_name, value) {
-                world = value
+    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
+        match interaction.data.autocomplete() {
+            Some(AutocompleteOption { name: "world", value, .. }) => bot.autocomplete_worlds(ctx, &interaction, value).await,
+            Some(_) | None => {
+                interaction.no_autocomplete(&ctx).await;
             }
         }
+    }
+}
 
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
+impl FinishWorldCommand {
+    pub async fn run(bot: &Bot, ctx: &impl Discord, command: &CommandInteraction) {
+        let Some(Options { world }) = Options::parse(ctx, command).await else {
             return;
-        }
+        };
 
         if !bot.admins.contains(&command.user.id) {
-            command.simple_reply(&ctx, "You do not have permission to use this command").await;
+            command.simple_reply(ctx, "You do not have permission to use this command").await;
             return;
         }
 
         let Ok(mut transaction) = bot.db.begin().await else {
-            command.simple_reply(&ctx, "Failed to create transaction").await;
+            command.simple_reply(ctx, "Failed to create transaction").await;
             return;
         };
 
-        let _ = command.defer_ephemeral(&ctx.http).await;
+        let _ = ctx.defer_ephemeral(command).await;
 
         let mut output = vec![];
+        let mut awards = vec![];
 
-        if let Err(err) = query!(
-            "DELETE FROM updates WHERE slot IN (SELECT id FROM tracked_slots WHERE world IN (SELECT id FROM tracked_worlds WHERE name = ?))",
+        let world_id = match query!(
+            "UPDATE tracked_worlds SET archived = 1, archived_at = strftime('%s', 'now') WHERE name = ? AND archived = 0 RETURNING id AS \"id!\"",
             world
         )
-        .execute(&mut *transaction)
+        .fetch_optional(&mut *transaction)
         .await
         {
-            println!("Failed to delete status updates: {err}");
-            let _ = transaction.rollback().await;
-            let _ = command
-                .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to delete status updates. Aborting"))
-                .await;
-            return;
-        }
+            Ok(Some(response)) => response.id,
+            Ok(None) => {
+                let _ = transaction.rollback().await;
+                let _ = ctx.edit_response(&command.token, EditInteractionResponse::new().content("Failed to find world. Aborting")).await;
+                return;
+            }
+            Err(err) => {
+                println!("Failed to archive world: {err}");
+                let _ = transaction.rollback().await;
+                let _ = ctx.edit_response(&command.token, EditInteractionResponse::new().content("Failed to archive world. Aborting")).await;
+                return;
+            }
+        };
 
-        if let Ok(slot_response) = query!("SELECT id, name, games, points FROM tracked_slots WHERE world in (SELECT id FROM tracked_worlds WHERE name = ?)", world)
+        if let Ok(slot_response) = query!("SELECT id, name, games, points, status FROM tracked_slots WHERE world = ?", world_id)
             .fetch_all(&mut *transaction)
             .await
         {
             for record in slot_response {
-                let points_recipient = match query!("SELECT id, transfer_to FROM players WHERE id IN (SELECT player FROM claims WHERE slot = ?) LIMIT 1", record.id)
+                let (claimant, points_recipient) = match query!("SELECT id, transfer_to FROM players WHERE id IN (SELECT player FROM claims WHERE slot = ?) LIMIT 1", record.id)
                     .fetch_optional(&bot.db)
                     .await
                 {
-                    Ok(response) => response.map(|record| record.transfer_to.unwrap_or(record.id)),
+                    Ok(response) => (response.as_ref().map(|record| record.id), response.map(|record| record.transfer_to.unwrap_or(record.id))),
                     Err(err) => {
                         println!("Failed to get points recipient for slot {}: {err}", record.id);
                         let _ = transaction.rollback().await;
-                        let _ = command
-                            .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get points recipient for . Aborting"))
+                        let _ = ctx
+                            .edit_response(&command.token, EditInteractionResponse::new().content("Failed to get points recipient for . Aborting"))
                             .await;
                         return;
                     }
                 };
 
-                match query!("UPDATE players SET points = points + ? WHERE id = ? RETURNING snowflake", record.points, points_recipient)
-                    .fetch_optional(&mut *transaction)
-                    .await
+                match query!(
+                    "UPDATE players SET points = points + ?, season_points = season_points + ? WHERE id = ? RETURNING snowflake",
+                    record.points,
+                    record.points,
+                    points_recipient
+                )
+                .fetch_optional(&mut *transaction)
+                .await
                 {
                     Ok(response) => {
                         if let Some(response) = response {
                             output.push((record.name, Some(response.snowflake)));
 
-                            if let Err(err) = query!("DELETE FROM claims WHERE slot = ?", record.id).execute(&mut *transaction).await {
-                                println!("Failed to delete claim: {err}");
+                            let status = Status::from_i64(record.status);
+                            let completed = matches!(status, Some(Status::Goal | Status::AllChecks | Status::Done));
+                            let goal = matches!(status, Some(Status::Goal | Status::Done));
+                            if let Err(err) = query!(
+                                "UPDATE players SET slots_completed = slots_completed + ?, goals_reached = goals_reached + ? WHERE id = ?",
+                                completed,
+                                goal,
+                                claimant
+                            )
+                            .execute(&mut *transaction)
+                            .await
+                            {
+                                println!("Failed to update completion stats: {err}");
+                                let _ = transaction.rollback().await;
+                                let _ = ctx
+                                    .edit_response(&command.token, EditInteractionResponse::new().content("Failed to update completion stats. Aborting"))
+                                    .await;
+                                return;
+                            }
+
+                            if let Err(err) = query!("UPDATE tracked_slots SET awarded_to = ? WHERE id = ?", points_recipient, record.id)
+                                .execute(&mut *transaction)
+                                .await
+                            {
+                                println!("Failed to record points recipient: {err}");
                                 let _ = transaction.rollback().await;
-                                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to delete claim. Aborting")).await;
+                                let _ = ctx
+                                    .edit_response(&command.token, EditInteractionResponse::new().content("Failed to record points recipient. Aborting"))
+                                    .await;
                                 return;
                             }
+
+                            if let (Some(claimant), Some(recipient)) = (claimant, points_recipient) {
+                                awards.push(Award {
+                                    slot: record.id,
+                                    claimant,
+                                    recipient,
+                                    points: record.points,
+                                    completed,
+                                    goal,
+                                });
+                            }
                         } else {
                             output.push((record.name, None));
                         }
-
-                        if let Err(err) = query!("DELETE FROM tracked_slots WHERE id = ?", record.id).execute(&mut *transaction).await {
-                            println!("Failed to delete slot: {err}");
-                            let _ = transaction.rollback().await;
-                            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to delete slot. Aborting")).await;
-                            return;
-                        }
                     }
                     Err(err) => {
                         println!("Failed to update points: {err}");
                         let _ = transaction.rollback().await;
-                        let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to update points. Aborting")).await;
+                        let _ = ctx.edit_response(&command.token, EditInteractionResponse::new().content("Failed to update points. Aborting")).await;
                         return;
                     }
                 }
             }
         } else {
             let _ = transaction.rollback().await;
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get slots")).await;
+            let _ = ctx.edit_response(&command.token, EditInteractionResponse::new().content("Failed to get slots")).await;
             return;
         }
 
-        if let Err(err) = query!("DELETE FROM tracked_worlds WHERE name = ?", world).execute(&mut *transaction).await {
-            println!("Failed to delete world: {err}");
-            let _ = transaction.rollback().await;
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to delete world. Aborting")).await;
-            return;
-        }
+        let preclaim_world = match PreclaimWorld::fetch(&mut transaction, &world).await {
+            Ok(preclaim_world) => preclaim_world,
+            Err(err) => {
+                println!("Failed to get preclaim world: {err}");
+                let _ = transaction.rollback().await;
+                let _ = ctx
+                    .edit_response(&command.token, EditInteractionResponse::new().content("Failed to get preclaim world. Aborting"))
+                    .await;
+                return;
+            }
+        };
 
         if let Err(err) = query!("DELETE FROM worlds WHERE name = ?", world).execute(&mut *transaction).await {
             println!("Failed to delete preclaim world: {err}");
             let _ = transaction.rollback().await;
-            let _ = command
-                .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to delete preclaim world. Aborting"))
+            let _ = ctx
+                .edit_response(&command.token, EditInteractionResponse::new().content("Failed to delete preclaim world. Aborting"))
                 .await;
             return;
         }
 
-        let Some(status_channel) = Bot::status_channel(&ctx).await else {
+        let season = match query!("SELECT MAX(id) AS season FROM seasons").fetch_one(&mut *transaction).await {
+            Ok(response) => response.season,
+            Err(err) => {
+                println!("Failed to get current season: {err}");
+                let _ = transaction.rollback().await;
+                let _ = ctx
+                    .edit_response(&command.token, EditInteractionResponse::new().content("Failed to get current season. Aborting"))
+                    .await;
+                return;
+            }
+        };
+
+        if let Err(err) = (UndoAction::FinishWorld {
+            world: world_id,
+            awards,
+            preclaim_world,
+            season,
+        })
+        .record(&mut transaction, i64::from(command.user.id), Self::NAME, &format!("Finished world {world}"))
+        .await
+        {
+            println!("Failed to record undo entry: {err}");
             let _ = transaction.rollback().await;
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get status channel. Aborting")).await;
+            let _ = ctx.edit_response(&command.token, EditInteractionResponse::new().content("Failed to record undo entry. Aborting")).await;
             return;
-        };
+        }
+
+        let status_channel = ChannelId::new(STATUS_CHANNEL);
 
         let mut iter = output.into_iter().array_chunks::<50>();
         for chunk in iter.by_ref() {
-            if let Err(err) = status_channel
+            if let Err(err) = ctx
                 .send_message(
-                    &ctx,
+                    status_channel,
                     CreateMessage::new().embed(
                         CreateEmbed::new().title(format!("{world} completed!")).description(
                             chunk
@@ -161,8 +247,8 @@ impl Command for FinishWorldCommand {
             {
                 println!("Failed to post completion to status channel: {err}");
                 let _ = transaction.rollback().await;
-                let _ = command
-                    .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to post completion to status channel. Aborting"))
+                let _ = ctx
+                    .edit_response(&command.token, EditInteractionResponse::new().content("Failed to post completion to status channel. Aborting"))
                     .await;
                 return;
             }
@@ -170,9 +256,9 @@ impl Command for FinishWorldCommand {
 
         let chunk: Vec<_> = iter.into_remainder().collect();
         if !chunk.is_empty() {
-            if let Err(err) = status_channel
+            if let Err(err) = ctx
                 .send_message(
-                    &ctx,
+                    status_channel,
                     CreateMessage::new().embed(
                         CreateEmbed::new().title(format!("{world} completed!")).description(
                             chunk
@@ -187,8 +273,8 @@ impl Command for FinishWorldCommand {
             {
                 println!("Failed to post completion to status channel: {err}");
                 let _ = transaction.rollback().await;
-                let _ = command
-                    .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to post completion to status channel. Aborting"))
+                let _ = ctx
+                    .edit_response(&command.token, EditInteractionResponse::new().content("Failed to post completion to status channel. Aborting"))
                     .await;
                 return;
             }
@@ -196,22 +282,16 @@ impl Command for FinishWorldCommand {
 
         if let Err(err) = transaction.commit().await {
             println!("Failed to commit transaction: {err}");
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to commit transaction. Aborting")).await;
+            let _ = ctx
+                .edit_response(&command.token, EditInteractionResponse::new().content("Failed to commit transaction. Aborting"))
+                .await;
             return;
         }
 
+        bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Finished and archived").world(&world)).await;
         bot.push_needed().await;
-        let _ = command
-            .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Successfully finished world {world}")))
+        let _ = ctx
+            .edit_response(&command.token, EditInteractionResponse::new().content(format!("Successfully finished world {world}")))
             .await;
     }
-
-    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
-        match interaction.data.autocomplete() {
-            Some(AutocompleteOption { name: "world", value, .. }) => bot.autocomplete_worlds(ctx, &interaction, value).await,
-            Some(_) | None => {
-                interaction.no_autocomplete(&ctx).await;
-            }
-        }
-    }
 }
@@ -5,12 +5,18 @@ use std::{
 
 use rand::{seq::SliceRandom, thread_rng};
 use serenity::{
-    all::{AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, ResolvedOption, ResolvedValue},
+    all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand},
     futures::future::join_all,
 };
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot, UNSPENT_POINTS_LIMIT};
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, commands::Command, options::command_options, util::SimpleReply, Bot, UNSPENT_POINTS_LIMIT};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+    }
+}
 
 pub struct GetPreclaimsCommand {}
 
@@ -18,10 +24,7 @@ impl Command for GetPreclaimsCommand {
     const NAME: &'static str = "get-preclaims";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Gets preclaims for a world")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Gets preclaims for a world").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -32,18 +35,9 @@ impl Command for GetPreclaimsCommand {
             return;
         }
 
-        let mut name = "";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            if let ("world", ResolvedValue::String(value)) = (option_name, value) {
-                name = value
-            }
-        }
-
-        if name.is_empty() {
-            command.simple_reply(&ctx, "A world name is required").await;
+        let Some(Options { world: name }) = Options::parse(&ctx, &command).await else {
             return;
-        }
+        };
 
         if let Ok(response) = query!("SELECT preclaim_end FROM worlds WHERE name = ? LIMIT 1", name).fetch_one(&bot.db).await {
             let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
@@ -57,13 +51,16 @@ impl Command for GetPreclaimsCommand {
             return;
         }
 
-        let selected_preclaims = if let Some(selected_preclaims) = resolve_preclaims(bot, name).await {
+        let selected_preclaims = if let Some(selected_preclaims) = resolve_preclaims(bot, &name).await {
             selected_preclaims
         } else {
             command.simple_reply(&ctx, "Failed to resolve preclaims").await;
             return;
         };
 
+        bot.audit(AuditEvent::new(user, Self::NAME, format!("Resolved {} preclaims", selected_preclaims.len())).world(&name))
+            .await;
+
         if selected_preclaims.is_empty() {
             command.simple_reply(&ctx, "World had no preclaims").await;
         } else {
@@ -0,0 +1,106 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, User};
+use sqlx::query;
+
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        player: User = ("player", "Player to grant claims to"),
+        reality: String = ("reality", "Name of the reality").set_autocomplete(true),
+        claims: i64 = ("claims", "Number of extra claims"),
+        expires: i64 = ("expires", "Unix timestamp when the extra claims expire"),
+    }
+}
+
+pub struct GrantClaimsCommand {}
+
+impl Command for GrantClaimsCommand {
+    const NAME: &'static str = "grant-claims";
+
+    fn register() -> CreateCommand {
+        Options::add_options(
+            CreateCommand::new(Self::NAME)
+                .description("Temporarily allows a player to hold extra claims in a reality")
+                .kind(CommandType::ChatInput),
+        )
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let user = command.user.id;
+
+        if !bot.admins.contains(&user) {
+            command.simple_reply(&ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(Options {
+            player: target,
+            reality,
+            claims,
+            expires,
+        }) = Options::parse(&ctx, &command).await
+        else {
+            return;
+        };
+
+        if claims < 1 {
+            command.simple_reply(&ctx, "Claims must be a positive integer").await;
+            return;
+        }
+
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        if current_time.as_secs() as i64 > expires {
+            command.simple_reply(&ctx, "The specified expiry is in the past").await;
+            return;
+        }
+
+        let Some(player) = bot.get_player(i64::from(target.id), &target.name).await else {
+            command.simple_reply(&ctx, "Failed to get player").await;
+            return;
+        };
+
+        let Ok(Some(reality_id)) = query!("SELECT id FROM realities WHERE name = ? AND archived = 0 LIMIT 1", reality)
+            .fetch_optional(&bot.db)
+            .await
+            .map(|response| response.map(|record| record.id))
+        else {
+            command.simple_reply(&ctx, "Failed to get reality").await;
+            return;
+        };
+
+        let granted_by = i64::from(user);
+        if let Err(err) = query!(
+            "INSERT INTO claim_grants (player, reality, claims, expires, granted_by) VALUES (?, ?, ?, ?, ?)",
+            player.id,
+            reality_id,
+            claims,
+            expires,
+            granted_by
+        )
+        .execute(&bot.db)
+        .await
+        {
+            println!("Failed to grant claims: {err}");
+            command.simple_reply(&ctx, "Failed to grant claims").await;
+            return;
+        }
+
+        bot.audit(AuditEvent::new(user, Self::NAME, format!("Granted {claims} extra claims in {reality} until {expires}")).target(target.id))
+            .await;
+        command
+            .simple_reply(&ctx, format!("Granted <@{}> {claims} extra claims in {reality} until <t:{expires}:f>", target.id))
+            .await;
+    }
+
+    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
+        match interaction.data.autocomplete() {
+            Some(AutocompleteOption { name: "reality", value, .. }) => bot.autocomplete_realities(ctx, &interaction, value).await,
+            Some(_) | None => {
+                interaction.no_autocomplete(&ctx).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use serenity::all::{Colour, CommandInteraction, CommandType, ComponentInteraction, Context, CreateCommand, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, ModalInteraction, Timestamp};
+use sqlx::query;
+
+use crate::{
+    commands::Command,
+    custom_id::{CustomId, Fields},
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
+    util::SimpleReply,
+    Bot,
+};
+
+#[derive(Clone, Copy)]
+pub enum Category {
+    Points,
+    SeasonPoints,
+    Slots,
+    Goals,
+}
+
+pub struct Leaderboard {
+    category: Category,
+    season: Option<(String, i64)>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone)]
+pub struct Entry {
+    rank: usize,
+    snowflake: i64,
+    value: i64,
+    category: Category,
+}
+
+command_options! {
+    struct Options {
+        category: Option<String> = ("category", "What to rank players by. Defaults to points this season")
+            .add_string_choice("Points this season", "season-points")
+            .add_string_choice("All-time points", "points")
+            .add_string_choice("Slots completed", "slots")
+            .add_string_choice("Goals reached", "goals"),
+    }
+}
+
+pub struct LeaderboardCommand {}
+
+impl Command for LeaderboardCommand {
+    const NAME: &'static str = "leaderboard";
+
+    fn register() -> CreateCommand {
+        Options::add_options(CreateCommand::new(Self::NAME).description("View the leaderboard").kind(CommandType::ChatInput))
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let Some(Options { category }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let Some(category) = Category::try_from(category.as_deref().unwrap_or("season-points")) else {
+            command.simple_reply(&ctx, "Invalid category").await;
+            return;
+        };
+
+        let Some(response) = Self::first_page(bot, category).await else {
+            command.simple_reply(&ctx, "Nobody is on this leaderboard yet").await;
+            return;
+        };
+
+        let _ = command.create_response(&ctx.http, CreateInteractionResponse::Message(response.into())).await;
+    }
+}
+
+impl Category {
+    fn try_from(value: &str) -> Option<Self> {
+        match value {
+            "points" => Some(Category::Points),
+            "season-points" => Some(Category::SeasonPoints),
+            "slots" => Some(Category::Slots),
+            "goals" => Some(Category::Goals),
+            _ => None,
+        }
+    }
+
+    fn as_i(self) -> usize {
+        match self {
+            Category::Points => 0,
+            Category::SeasonPoints => 1,
+            Category::Slots => 2,
+            Category::Goals => 3,
+        }
+    }
+
+    fn try_from_i(value: usize) -> Option<Category> {
+        match value {
+            0 => Some(Category::Points),
+            1 => Some(Category::SeasonPoints),
+            2 => Some(Category::Slots),
+            3 => Some(Category::Goals),
+            _ => None,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Category::Points => "All-time points",
+            Category::SeasonPoints => "Points this season",
+            Category::Slots => "Slots completed",
+            Category::Goals => "Goals reached",
+        }
+    }
+
+    fn unit(self) -> &'static str {
+        match self {
+            Category::Points | Category::SeasonPoints => "points",
+            Category::Slots => "slots",
+            Category::Goals => "goals",
+        }
+    }
+}
+
+impl Paginate<Leaderboard, Entry, Entry, Category> for LeaderboardCommand {
+    const PAGE_SIZE: usize = 24;
+
+    async fn get_containers(bot: &Bot, category: Category, _view: View) -> Vec<Leaderboard> {
+        let Ok(standings) = (match category {
+            Category::Points => query!("SELECT snowflake, points AS value FROM players WHERE points > 0 ORDER BY points DESC, name ASC")
+                .fetch_all(&bot.db)
+                .await
+                .map(|response| response.into_iter().map(|record| (record.snowflake, record.value)).collect::<Vec<_>>()),
+            Category::SeasonPoints => query!("SELECT snowflake, season_points AS value FROM players WHERE season_points > 0 ORDER BY season_points DESC, name ASC")
+                .fetch_all(&bot.db)
+                .await
+                .map(|response| response.into_iter().map(|record| (record.snowflake, record.value)).collect::<Vec<_>>()),
+            Category::Slots => query!("SELECT snowflake, slots_completed AS value FROM players WHERE slots_completed > 0 ORDER BY slots_completed DESC, name ASC")
+                .fetch_all(&bot.db)
+                .await
+                .map(|response| response.into_iter().map(|record| (record.snowflake, record.value)).collect::<Vec<_>>()),
+            Category::Goals => query!("SELECT snowflake, goals_reached AS value FROM players WHERE goals_reached > 0 ORDER BY goals_reached DESC, name ASC")
+                .fetch_all(&bot.db)
+                .await
+                .map(|response| response.into_iter().map(|record| (record.snowflake, record.value)).collect::<Vec<_>>()),
+        }) else {
+            return vec![];
+        };
+
+        if standings.is_empty() {
+            return vec![];
+        }
+
+        let season = if let Category::SeasonPoints = category {
+            query!("SELECT name, started FROM seasons ORDER BY id DESC LIMIT 1")
+                .fetch_optional(&bot.db)
+                .await
+                .ok()
+                .flatten()
+                .map(|record| (record.name, record.started))
+        } else {
+            None
+        };
+
+        let mut entries: Vec<Entry> = Vec::with_capacity(standings.len());
+        for (i, (snowflake, value)) in standings.into_iter().enumerate() {
+            let rank = match entries.last() {
+                Some(previous) if previous.value == value => previous.rank,
+                _ => i + 1,
+            };
+
+            entries.push(Entry { rank, snowflake, value, category });
+        }
+
+        vec![Leaderboard { category, season, entries }]
+    }
+
+    fn id_prefix(category: Category) -> CustomId {
+        CustomId::new(Self::NAME).push(category.as_i())
+    }
+}
+
+impl PageContainer<Entry, Entry, Category> for Leaderboard {
+    fn items(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    fn page_setup(&self) -> CreateEmbed {
+        let embed = CreateEmbed::new().title(self.category.title()).colour(Colour::DARK_PURPLE);
+
+        if let Some((name, started)) = &self.season {
+            embed
+                .footer(CreateEmbedFooter::new(format!("Season: {name}")))
+                .timestamp(Timestamp::from_unix_timestamp(*started).unwrap_or_default())
+        } else {
+            embed
+        }
+    }
+}
+
+impl PageItem<Entry, Category> for Entry {
+    async fn details(&self, _bot: &Bot, _extra: Category) -> Option<Entry> {
+        Some(self.clone())
+    }
+}
+
+impl PageDetails for Entry {
+    fn field(&self) -> (String, String, bool) {
+        (format!("#{}", self.rank), format!("<@{}>\n{} {}", self.snowflake, self.value, self.category.unit()), true)
+    }
+}
+
+impl LeaderboardCommand {
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, mut fields: Fields<'_>) {
+        let Some(category) = fields.next().and_then(Category::try_from_i) else {
+            interaction.simple_reply(&ctx, "Malformed leaderboard category").await;
+            return;
+        };
+
+        if !Self::try_handle_interaction(bot, &ctx, interaction, fields, category).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, mut fields: Fields<'_>) {
+        let Some(category) = fields.next().and_then(Category::try_from_i) else {
+            interaction.simple_reply(&ctx, "Malformed leaderboard category").await;
+            return;
+        };
+
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, category).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use serenity::all::{ChannelId, CommandInteraction, CommandType, Context, CreateCommand, CreateMessage, EditInteractionResponse, MessageId};
+use sqlx::query;
+
+use crate::{audit::AuditEvent, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        channel: ChannelId = ("channel", "Channel to post the board in"),
+        remove: Option<bool> = ("remove", "Remove the board from the channel instead"),
+    }
+}
+
+pub struct LiveBoardCommand {}
+
+impl Command for LiveBoardCommand {
+    const NAME: &'static str = "live-board";
+
+    fn register() -> CreateCommand {
+        Options::add_options(
+            CreateCommand::new(Self::NAME)
+                .description("Post a pinned board in a channel that keeps itself updated with world progress")
+                .kind(CommandType::ChatInput),
+        )
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let user = command.user.id;
+
+        if !bot.admins.contains(&user) {
+            command.simple_reply(&ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(Options { channel, remove }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let remove = remove.unwrap_or_default();
+
+        let channel_i = i64::from(channel);
+
+        let existing = match query!("SELECT id, message FROM live_boards WHERE channel = ? LIMIT 1", channel_i).fetch_optional(&bot.db).await {
+            Ok(existing) => existing,
+            Err(_) => {
+                command.simple_reply(&ctx, "Failed to get live boards").await;
+                return;
+            }
+        };
+
+        let _ = command.defer_ephemeral(&ctx.http).await;
+
+        if remove {
+            let Some(existing) = existing else {
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("There is no live board in that channel")).await;
+                return;
+            };
+
+            if query!("DELETE FROM live_boards WHERE id = ?", existing.id).execute(&bot.db).await.is_err() {
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to remove live board")).await;
+                return;
+            }
+
+            let _ = ChannelId::new(channel_i as u64).delete_message(&ctx.http, MessageId::new(existing.message as u64)).await;
+
+            bot.audit(AuditEvent::new(user, Self::NAME, format!("Removed live board from <#{channel}>"))).await;
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Removed live board")).await;
+            return;
+        }
+
+        if existing.is_some() {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("That channel already has a live board")).await;
+            return;
+        }
+
+        let Some(embed) = bot.live_board_embed().await else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to build live board")).await;
+            return;
+        };
+
+        let message = match channel.send_message(&ctx.http, CreateMessage::new().embed(embed)).await {
+            Ok(message) => message,
+            Err(err) => {
+                println!("Failed to post live board: {err}");
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to post live board")).await;
+                return;
+            }
+        };
+
+        let message_i = i64::from(message.id);
+        let user_i = i64::from(user);
+        if query!("INSERT INTO live_boards (channel, message, created_by) VALUES (?, ?, ?)", channel_i, message_i, user_i)
+            .execute(&bot.db)
+            .await
+            .is_err()
+        {
+            let _ = message.delete(&ctx.http).await;
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to save live board")).await;
+            return;
+        }
+
+        bot.audit(AuditEvent::new(user, Self::NAME, format!("Posted live board in <#{channel}>"))).await;
+
+        let _ = command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(if message.pin(&ctx.http).await.is_ok() {
+                    "Posted live board"
+                } else {
+                    "Posted live board, but failed to pin it"
+                }),
+            )
+            .await;
+    }
+}
@@ -1,7 +1,14 @@
-use serenity::all::{AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, EditInteractionResponse, ResolvedOption, ResolvedValue};
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, EditInteractionResponse, ResolvedOption, ResolvedValue};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, commands::Command, options::command_options, undo::UndoAction, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+        slot: String = ("slot", "Name of the slot").set_autocomplete(true),
+    }
+}
 
 pub struct MarkFreeCommand {}
 
@@ -9,11 +16,7 @@ impl Command for MarkFreeCommand {
     const NAME: &'static str = "mark-free";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Marks a slot in a world as a free claim")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot", "Name of the slot").required(true).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Marks a slot in a world as a free claim").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -24,45 +27,61 @@ impl Command for MarkFreeCommand {
             return;
         }
 
-        let mut world = "";
-        let mut slot = "";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("slot", ResolvedValue::String(value)) => slot = value,
-                _ => (),
-            }
-        }
-
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
+        let Some(Options { world, slot }) = Options::parse(&ctx, &command).await else {
             return;
-        }
-
-        if slot.is_empty() {
-            command.simple_reply(&ctx, "A slot is required").await;
-            return;
-        }
+        };
 
         let _ = command.defer_ephemeral(&ctx.http).await;
 
-        if query!(
-            "UPDATE tracked_slots SET free = 1 WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ?)",
+        let Ok(mut transaction) = bot.db.begin().await else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to create transaction")).await;
+            return;
+        };
+
+        let Ok(Some(previous)) = query!(
+            "SELECT id, free FROM tracked_slots WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0) LIMIT 1",
             slot,
             world
         )
-        .execute(&bot.db)
+        .fetch_optional(&mut *transaction)
         .await
-        .is_ok()
-        {
-            bot.push_needed().await;
-            let _ = command
-                .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Successfully marked {slot} in {world} as a free claim")))
-                .await;
-        } else {
+        else {
+            let _ = transaction.rollback().await;
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to find slot")).await;
+            return;
+        };
+
+        if let Err(err) = query!("UPDATE tracked_slots SET free = 1 WHERE id = ?", previous.id).execute(&mut *transaction).await {
+            println!("Failed to mark slot as a free claim: {err}");
+            let _ = transaction.rollback().await;
             let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to mark slot as a free claim")).await;
+            return;
         }
+
+        if let Err(err) = (UndoAction::MarkFree {
+            slot: previous.id,
+            free: previous.free,
+        })
+        .record(&mut transaction, i64::from(user), Self::NAME, &format!("Marked {slot} in {world} as a free claim"))
+        .await
+        {
+            println!("Failed to record undo entry: {err}");
+            let _ = transaction.rollback().await;
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to record undo entry. Aborting")).await;
+            return;
+        }
+
+        if let Err(err) = transaction.commit().await {
+            println!("Failed to commit transaction: {err}");
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to commit transaction. Aborting")).await;
+            return;
+        }
+
+        bot.audit(AuditEvent::new(user, Self::NAME, "Marked as a free claim").world(&world).slot(&slot)).await;
+        bot.push_needed().await;
+        let _ = command
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Successfully marked {slot} in {world} as a free claim")))
+            .await;
     }
 
     async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
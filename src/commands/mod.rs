@@ -1,3 +1,5 @@
+pub mod archived_worlds;
+pub mod audit;
 pub mod bulk_status;
 pub mod cancel_preclaims;
 pub mod claim;
@@ -6,17 +8,26 @@ pub mod done;
 pub mod find;
 pub mod finish_world;
 pub mod get_preclaims;
+pub mod grant_claims;
+pub mod leaderboard;
+pub mod live_board;
 pub mod mark_free;
+pub mod my_claims_status;
 pub mod new_reality;
+pub mod new_season;
 pub mod new_world;
+pub mod profile;
 pub mod public;
+pub mod reality;
 pub mod register_commands;
 pub mod reschedule_preclaims;
+pub mod search;
 pub mod status;
 pub mod status_report;
 pub mod track_world;
 pub mod unclaim;
 pub mod unclaimed;
+pub mod undo;
 pub mod unpreclaim;
 pub mod view_preclaims;
 pub mod worlds;
@@ -25,109 +36,222 @@ pub mod transfer_points;
 use crate::{
     autocomplete::Autocomplete,
     commands::{
-        bulk_status::BulkStatusCommand, cancel_preclaims::CancelPreclaimsCommand, claim::ClaimCommand, claimed::ClaimedCommand, done::DoneCommand, find::FindCommand, finish_world::FinishWorldCommand, get_preclaims::GetPreclaimsCommand, mark_free::MarkFreeCommand, new_reality::NewRealityCommand, new_world::NewWorldCommand, public::PublicCommand, register_commands::RegisterCommandsCommand, reschedule_preclaims::ReschedulePreclaimsCommand, status::StatusCommand, status_report::StatusReportCommand, track_world::TrackWorldCommand, transfer_points::TransferPointsCommand, unclaim::UnclaimCommand, unclaimed::UnclaimedCommand, unpreclaim::UnpreclaimCommand, view_preclaims::ViewPreclaimsCommand, worlds::WorldsCommand
+        archived_worlds::ArchivedWorldsCommand, audit::AuditCommand, bulk_status::BulkStatusCommand, cancel_preclaims::CancelPreclaimsCommand, claim::ClaimCommand, claimed::ClaimedCommand,
+        done::DoneCommand, find::FindCommand, finish_world::FinishWorldCommand, get_preclaims::GetPreclaimsCommand, grant_claims::GrantClaimsCommand, leaderboard::LeaderboardCommand,
+        live_board::LiveBoardCommand, mark_free::MarkFreeCommand, my_claims_status::MyClaimsStatusCommand, new_reality::NewRealityCommand, new_season::NewSeasonCommand, new_world::NewWorldCommand,
+        profile::ProfileCommand, public::PublicCommand, reality::RealityCommand, register_commands::RegisterCommandsCommand, reschedule_preclaims::ReschedulePreclaimsCommand, search::SearchCommand,
+        status::StatusCommand, status_report::StatusReportCommand, track_world::TrackWorldCommand, transfer_points::TransferPointsCommand, unclaim::UnclaimCommand, unclaimed::UnclaimedCommand,
+        undo::UndoCommand, unpreclaim::UnpreclaimCommand, view_preclaims::ViewPreclaimsCommand, worlds::WorldsCommand,
     },
 };
-use serenity::all::{Command as SerenityCommand, CommandInteraction, Context, CreateCommand, Interaction};
-
-use crate::Bot;
-
-pub async fn register_all(ctx: &Context) -> Result<Vec<SerenityCommand>, serenity::Error> {
-    SerenityCommand::set_global_commands(
-        &ctx.http,
-        vec![
-            ViewPreclaimsCommand::register(),
-            NewWorldCommand::register(),
-            GetPreclaimsCommand::register(),
-            TrackWorldCommand::register(),
-            ClaimCommand::register(),
-            StatusCommand::register(),
-            StatusReportCommand::register(),
-            UnclaimCommand::register(),
-            MarkFreeCommand::register(),
-            PublicCommand::register(),
-            UnclaimedCommand::register(),
-            ClaimedCommand::register(),
-            FinishWorldCommand::register(),
-            ReschedulePreclaimsCommand::register(),
-            CancelPreclaimsCommand::register(),
-            WorldsCommand::register(),
-            DoneCommand::register(),
-            BulkStatusCommand::register(),
-            FindCommand::register(),
-            RegisterCommandsCommand::register(),
-            UnpreclaimCommand::register(),
-            NewRealityCommand::register(),
-            TransferPointsCommand::register()
-        ],
-    )
-    .await
+use serde_json::{json, to_value, Value};
+use serenity::all::{Command as SerenityCommand, CommandInteraction, Context, CreateCommand, GuildId, Interaction};
+
+use crate::{custom_id::decode, util::SimpleReply, Bot};
+
+#[derive(Default)]
+pub struct RegisterDiff {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
 }
 
-pub async fn interaction_create(bot: &Bot, ctx: Context, interaction: Interaction) {
-    match interaction {
-        Interaction::Command(command) => match command.data.name.as_str() {
-            ViewPreclaimsCommand::NAME => ViewPreclaimsCommand::execute(bot, ctx, command).await,
-            NewWorldCommand::NAME => NewWorldCommand::execute(bot, ctx, command).await,
-            GetPreclaimsCommand::NAME => GetPreclaimsCommand::execute(bot, ctx, command).await,
-            TrackWorldCommand::NAME => TrackWorldCommand::execute(bot, ctx, command).await,
-            ClaimCommand::NAME => ClaimCommand::execute(bot, ctx, command).await,
-            StatusCommand::NAME => StatusCommand::execute(bot, ctx, command).await,
-            StatusReportCommand::NAME => StatusReportCommand::execute(bot, ctx, command).await,
-            UnclaimCommand::NAME => UnclaimCommand::execute(bot, ctx, command).await,
-            MarkFreeCommand::NAME => MarkFreeCommand::execute(bot, ctx, command).await,
-            PublicCommand::NAME => PublicCommand::execute(bot, ctx, command).await,
-            UnclaimedCommand::NAME => UnclaimedCommand::execute(bot, ctx, command).await,
-            ClaimedCommand::NAME => ClaimedCommand::execute(bot, ctx, command).await,
-            FinishWorldCommand::NAME => FinishWorldCommand::execute(bot, ctx, command).await,
-            ReschedulePreclaimsCommand::NAME => ReschedulePreclaimsCommand::execute(bot, ctx, command).await,
-            CancelPreclaimsCommand::NAME => CancelPreclaimsCommand::execute(bot, ctx, command).await,
-            WorldsCommand::NAME => WorldsCommand::execute(bot, ctx, command).await,
-            DoneCommand::NAME => DoneCommand::execute(bot, ctx, command).await,
-            BulkStatusCommand::NAME => BulkStatusCommand::execute(bot, ctx, command).await,
-            FindCommand::NAME => FindCommand::execute(bot, ctx, command).await,
-            RegisterCommandsCommand::NAME => RegisterCommandsCommand::execute(bot, ctx, command).await,
-            UnpreclaimCommand::NAME => UnpreclaimCommand::execute(bot, ctx, command).await,
-            NewRealityCommand::NAME => NewRealityCommand::execute(bot, ctx, command).await,
-            TransferPointsCommand::NAME => TransferPointsCommand::execute(bot, ctx, command).await,
-            _ => (),
-        },
-        Interaction::Component(component) => {
-            if let Some((_, rest)) = component.data.custom_id.split_once("view-preclaims-") {
-                ViewPreclaimsCommand::handle_interraction(bot, ctx, &component, rest).await;
-            } else if let Some((_, rest)) = component.data.custom_id.split_once("unclaimed-") {
-                UnclaimedCommand::handle_interraction(bot, ctx, &component, rest).await;
-            } else if let Some((_, rest)) = component.data.custom_id.split_once("status-report-") {
-                StatusReportCommand::handle_interraction(bot, ctx, &component, rest).await;
+pub async fn register_all(ctx: &Context, guild: Option<GuildId>) -> Result<RegisterDiff, serenity::Error> {
+    let mut existing = if let Some(guild) = guild {
+        guild.get_commands(&ctx.http).await?
+    } else {
+        SerenityCommand::get_global_commands(&ctx.http).await?
+    };
+    let mut diff = RegisterDiff::default();
+
+    for builder in all_commands() {
+        let new = to_value(&builder).map_err(serenity::Error::Json)?;
+        let name = new["name"].as_str().unwrap_or_default().to_owned();
+
+        if let Some(index) = existing.iter().position(|command| command.name == name) {
+            let command = existing.swap_remove(index);
+
+            if same_command(&new, &to_value(&command).map_err(serenity::Error::Json)?) {
+                diff.unchanged += 1;
+                continue;
+            }
+
+            if let Some(guild) = guild {
+                guild.edit_command(&ctx.http, command.id, builder).await?;
+            } else {
+                SerenityCommand::edit_global_command(&ctx.http, command.id, builder).await?;
+            }
+            diff.updated.push(name);
+        } else {
+            if let Some(guild) = guild {
+                guild.create_command(&ctx.http, builder).await?;
+            } else {
+                SerenityCommand::create_global_command(&ctx.http, builder).await?;
             }
+            diff.created.push(name);
+        }
+    }
+
+    for command in existing {
+        if let Some(guild) = guild {
+            guild.delete_command(&ctx.http, command.id).await?;
+        } else {
+            SerenityCommand::delete_global_command(&ctx.http, command.id).await?;
+        }
+        diff.deleted.push(command.name);
+    }
+
+    Ok(diff)
+}
+
+// Discord fills in defaults the builders leave out, so empty values count as missing. Anything else only the registered command has was removed from the builder
+fn same_command(new: &Value, existing: &Value) -> bool {
+    match (new, existing) {
+        (Value::Object(new), Value::Object(existing)) => {
+            new.iter().all(|(key, value)| same_command(value, existing.get(key).unwrap_or(&Value::Null)))
+                && existing
+                    .iter()
+                    .filter(|(key, _)| !new.contains_key(*key))
+                    .all(|(key, value)| is_empty(value) || is_generated(key, value))
         }
-        Interaction::Autocomplete(interaction) => match interaction.data.name.as_str() {
-            ViewPreclaimsCommand::NAME => ViewPreclaimsCommand::autocomplete(bot, ctx, interaction).await,
-            NewWorldCommand::NAME => NewWorldCommand::autocomplete(bot, ctx, interaction).await,
-            GetPreclaimsCommand::NAME => GetPreclaimsCommand::autocomplete(bot, ctx, interaction).await,
-            TrackWorldCommand::NAME => TrackWorldCommand::autocomplete(bot, ctx, interaction).await,
-            ClaimCommand::NAME => ClaimCommand::autocomplete(bot, ctx, interaction).await,
-            StatusCommand::NAME => StatusCommand::autocomplete(bot, ctx, interaction).await,
-            StatusReportCommand::NAME => StatusReportCommand::autocomplete(bot, ctx, interaction).await,
-            UnclaimCommand::NAME => UnclaimCommand::autocomplete(bot, ctx, interaction).await,
-            MarkFreeCommand::NAME => MarkFreeCommand::autocomplete(bot, ctx, interaction).await,
-            PublicCommand::NAME => PublicCommand::autocomplete(bot, ctx, interaction).await,
-            UnclaimedCommand::NAME => UnclaimedCommand::autocomplete(bot, ctx, interaction).await,
-            ClaimedCommand::NAME => ClaimedCommand::autocomplete(bot, ctx, interaction).await,
-            FinishWorldCommand::NAME => FinishWorldCommand::autocomplete(bot, ctx, interaction).await,
-            ReschedulePreclaimsCommand::NAME => ReschedulePreclaimsCommand::autocomplete(bot, ctx, interaction).await,
-            CancelPreclaimsCommand::NAME => CancelPreclaimsCommand::autocomplete(bot, ctx, interaction).await,
-            WorldsCommand::NAME => WorldsCommand::autocomplete(bot, ctx, interaction).await,
-            DoneCommand::NAME => DoneCommand::autocomplete(bot, ctx, interaction).await,
-            BulkStatusCommand::NAME => BulkStatusCommand::autocomplete(bot, ctx, interaction).await,
-            FindCommand::NAME => FindCommand::autocomplete(bot, ctx, interaction).await,
-            NewRealityCommand::NAME => NewRealityCommand::autocomplete(bot, ctx, interaction).await,
-            _ => (),
-        },
-        _ => (),
+        (Value::Array(new), Value::Array(existing)) => new.len() == existing.len() && new.iter().zip(existing).all(|(new, existing)| same_command(new, existing)),
+        (new, existing) if is_empty(new) && is_empty(existing) => true,
+        (new, existing) => new == existing,
     }
 }
 
+// Set by Discord rather than the builders: ids and versions, names localized for whoever fetched the command, and defaults for settings the bot never changes
+fn is_generated(key: &str, value: &Value) -> bool {
+    match key {
+        "id" | "application_id" | "version" | "guild_id" | "name_localized" | "description_localized" => true,
+        "dm_permission" => *value == Value::Bool(true),
+        "integration_types" => *value == json!([0]),
+        _ => false,
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Bool(value) => !value,
+        Value::Array(value) => value.is_empty(),
+        Value::Object(value) => value.is_empty(),
+        _ => false,
+    }
+}
+
+const STALE_ID_REPLY: &str = "This message is from an older version of the bot, run the command again to get an up to date one";
+
+// Every command is listed once here. Commands marked with `components` also handle buttons, selects and modals whose ids are built with their name
+macro_rules! commands {
+    ($($command:ident $($components:ident)?),* $(,)?) => {
+        pub fn all_commands() -> Vec<CreateCommand> {
+            vec![$($command::register()),*]
+        }
+
+        // The names interaction_create dispatches on
+        #[cfg(test)]
+        fn routed_names() -> Vec<&'static str> {
+            vec![$($command::NAME),*]
+        }
+
+        #[cfg(test)]
+        fn handles_components(name: &str) -> bool {
+            $(if commands!(@handles $($components)?) && name == $command::NAME {
+                return true;
+            })*
+
+            false
+        }
+
+        pub async fn interaction_create(bot: &Bot, ctx: Context, interaction: Interaction) {
+            match interaction {
+                Interaction::Command(command) => match command.data.name.as_str() {
+                    $($command::NAME => $command::execute(bot, ctx, command).await,)*
+                    _ => (),
+                },
+                Interaction::Component(component) => {
+                    let Ok((name, fields)) = decode(&component.data.custom_id) else {
+                        component.simple_reply(&ctx, STALE_ID_REPLY).await;
+                        return;
+                    };
+
+                    $(if name == $command::NAME {
+                        commands!(@component $command $($components)?, bot, ctx, &component, fields);
+                    })*
+                }
+                Interaction::Modal(modal) => {
+                    let Ok((name, fields)) = decode(&modal.data.custom_id) else {
+                        modal.simple_reply(&ctx, STALE_ID_REPLY).await;
+                        return;
+                    };
+
+                    $(if name == $command::NAME {
+                        commands!(@modal $command $($components)?, bot, ctx, &modal, fields);
+                    })*
+                }
+                Interaction::Autocomplete(interaction) => match interaction.data.name.as_str() {
+                    $($command::NAME => $command::autocomplete(bot, ctx, interaction).await,)*
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+    };
+    (@handles components) => {
+        true
+    };
+    (@handles) => {
+        false
+    };
+    (@component $command:ident components, $bot:ident, $ctx:ident, $interaction:expr, $fields:ident) => {
+        return $command::handle_interraction($bot, $ctx, $interaction, $fields).await
+    };
+    (@component $command:ident, $bot:ident, $ctx:ident, $interaction:expr, $fields:ident) => {};
+    (@modal $command:ident components, $bot:ident, $ctx:ident, $interaction:expr, $fields:ident) => {
+        return $command::handle_modal($bot, $ctx, $interaction, $fields).await
+    };
+    (@modal $command:ident, $bot:ident, $ctx:ident, $interaction:expr, $fields:ident) => {};
+}
+
+commands! {
+    ViewPreclaimsCommand components,
+    NewWorldCommand,
+    GetPreclaimsCommand,
+    TrackWorldCommand,
+    ClaimCommand,
+    StatusCommand,
+    StatusReportCommand components,
+    UnclaimCommand,
+    MarkFreeCommand,
+    PublicCommand components,
+    UnclaimedCommand components,
+    ClaimedCommand,
+    FinishWorldCommand,
+    ReschedulePreclaimsCommand,
+    CancelPreclaimsCommand,
+    WorldsCommand,
+    DoneCommand,
+    BulkStatusCommand,
+    FindCommand,
+    RegisterCommandsCommand,
+    UnpreclaimCommand,
+    NewRealityCommand,
+    TransferPointsCommand,
+    LeaderboardCommand components,
+    NewSeasonCommand,
+    ProfileCommand,
+    ArchivedWorldsCommand components,
+    UndoCommand,
+    AuditCommand components,
+    RealityCommand components,
+    MyClaimsStatusCommand,
+    GrantClaimsCommand,
+    SearchCommand components,
+    LiveBoardCommand,
+}
+
 pub trait Command {
     const NAME: &'static str;
 
@@ -138,3 +262,131 @@ pub trait Command {
         command.no_autocomplete(&ctx).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, to_value};
+    use serenity::all::{CommandOptionType, CreateCommand, CreateCommandOption};
+
+    use super::{
+        all_commands,
+        archived_worlds::ArchivedWorldsCommand,
+        audit::AuditCommand,
+        handles_components,
+        leaderboard::{Category, LeaderboardCommand},
+        public::PublicCommand,
+        reality::RealityCommand,
+        routed_names, same_command,
+        search::SearchCommand,
+        status_report::StatusReportCommand,
+        unclaimed::UnclaimedCommand,
+        view_preclaims::ViewPreclaimsCommand,
+        Command,
+    };
+    use crate::{
+        custom_id::{decode, CustomId, MAX_LENGTH},
+        paginate::Paginate,
+        Player,
+    };
+
+    // A builder registered under a different name than its NAME would show up in Discord but never be dispatched
+    #[test]
+    fn registered_commands_are_dispatched() {
+        let names = routed_names();
+        let registered: Vec<_> = all_commands().iter().map(|builder| to_value(builder).unwrap()["name"].as_str().unwrap().to_owned()).collect();
+
+        assert_eq!(registered.len(), names.len());
+        for name in &registered {
+            assert!(names.contains(&name.as_str()), "/{name} is registered but not dispatched");
+            assert_eq!(registered.iter().filter(|other| *other == name).count(), 1, "/{name} is registered twice");
+        }
+    }
+
+    // Ids are built by each command, so make sure every prefix a paginated command hands out routes back to a command that handles components
+    #[test]
+    fn paginated_ids_route_back_to_their_command() {
+        let player = Player {
+            id: 1,
+            name: String::from("player"),
+            unspent_points: 0,
+        };
+        let prefixes = [
+            (ViewPreclaimsCommand::NAME, ViewPreclaimsCommand::id_prefix(&player)),
+            (StatusReportCommand::NAME, StatusReportCommand::id_prefix(i64::MAX)),
+            (PublicCommand::NAME, PublicCommand::id_prefix(1)),
+            (UnclaimedCommand::NAME, UnclaimedCommand::id_prefix(())),
+            (LeaderboardCommand::NAME, LeaderboardCommand::id_prefix(Category::Goals)),
+            (ArchivedWorldsCommand::NAME, ArchivedWorldsCommand::id_prefix(())),
+            (AuditCommand::NAME, AuditCommand::id_prefix(1)),
+            (RealityCommand::NAME, RealityCommand::id_prefix(())),
+            (SearchCommand::NAME, SearchCommand::id_prefix(1)),
+        ];
+
+        for (name, prefix) in prefixes {
+            let id = prefix.push("page").push(1).push(0).push(0).build();
+            let Ok((command, _)) = decode(&id) else {
+                panic!("{id} should decode");
+            };
+
+            assert_eq!(command, name, "/{name} builds ids for another command");
+            assert!(handles_components(command), "/{name} builds ids but does not handle components");
+        }
+    }
+
+    #[test]
+    fn component_ids_route_to_their_command() {
+        for name in ["view-preclaims", "status-report", "public", "unclaimed", "leaderboard", "archived-worlds", "audit", "reality", "search"] {
+            let id = CustomId::new(name).push("page").push(1).push(0).push(0).build();
+            let Ok((command, mut fields)) = decode(&id) else {
+                panic!("{id} should decode");
+            };
+
+            assert_eq!(command, name);
+            assert!(handles_components(command), "/{name} does not handle components");
+            assert!(fields.tag("page"));
+            assert_eq!(fields.next::<usize>(), Some(1));
+        }
+
+        assert!(!handles_components("status"));
+        assert!(decode("status-report-5-page-1-0-0").is_err());
+        assert!(decode("search:0:claim:5").is_err());
+    }
+
+    #[test]
+    fn removed_settings_are_not_unchanged() {
+        let new = to_value(
+            CreateCommand::new("claim")
+                .description("Claims a slot")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "World")),
+        )
+        .unwrap();
+        let mut existing = new.clone();
+        existing["id"] = json!("1");
+        existing["application_id"] = json!("2");
+        existing["version"] = json!("3");
+        existing["guild_id"] = json!("4");
+        existing["dm_permission"] = json!(true);
+        existing["integration_types"] = json!([0]);
+        existing["nsfw"] = json!(false);
+        assert!(same_command(&new, &existing));
+
+        let mut localized = existing.clone();
+        localized["name_localizations"] = json!({ "fr": "reclamer" });
+        assert!(!same_command(&new, &localized));
+
+        let mut restricted = existing.clone();
+        restricted["default_member_permissions"] = json!("8");
+        assert!(!same_command(&new, &restricted));
+
+        let mut autocompleted = existing;
+        autocompleted["options"][0]["autocomplete"] = json!(true);
+        assert!(!same_command(&new, &autocompleted));
+    }
+
+    #[test]
+    fn component_ids_fit_discord_limit() {
+        let id = CustomId::new("archived-worlds").push(i64::MIN).push("page").push(u32::MAX).push(99).push(99).build();
+
+        assert!(id.len() <= MAX_LENGTH, "{id} is {} characters", id.len());
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use serenity::all::{Colour, CommandInteraction, CommandType, Context, CreateCommand, CreateEmbed, CreateEmbedFooter, EditInteractionResponse};
+use sqlx::query;
+
+use crate::{
+    commands::Command,
+    policy::{Reality, MAX_REALITIES, NO_REALITY_CLAIMS},
+    scrape::Status,
+    util::SimpleReply,
+    Bot,
+};
+
+const MAX_FIELD_LENGTH: usize = 1000;
+
+struct ClaimedSlot {
+    slot: String,
+    world: String,
+    status: i64,
+    last_scrape: i64,
+}
+
+impl ClaimedSlot {
+    fn line(&self) -> String {
+        format!(
+            "**{}** in {} ({}, updated <t:{}:R>)",
+            self.slot,
+            self.world,
+            Status::from_i64(self.status).map(|status| status.as_str()).unwrap_or("Unknown"),
+            self.last_scrape
+        )
+    }
+}
+
+pub struct MyClaimsStatusCommand {}
+
+impl Command for MyClaimsStatusCommand {
+    const NAME: &'static str = "my-claims-status";
+
+    fn register() -> CreateCommand {
+        CreateCommand::new(Self::NAME)
+            .description("Explains which of your claims count towards your claim limits")
+            .kind(CommandType::ChatInput)
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let Some(player) = bot.get_player(i64::from(command.user.id), &command.user.name).await else {
+            command.simple_reply(&ctx, "Failed to get user").await;
+            return;
+        };
+
+        let _ = command.defer_ephemeral(&ctx.http).await;
+
+        let Ok(points) = query!("SELECT points FROM players WHERE id = ? LIMIT 1", player.id)
+            .fetch_one(&bot.db)
+            .await
+            .map(|record| record.points)
+        else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get points")).await;
+            return;
+        };
+
+        let Ok(response) = query!(
+            "SELECT tracked_slots.name AS slot, tracked_worlds.name AS world, tracked_worlds.reality, free, status, last_scrape FROM claims
+            INNER JOIN tracked_slots ON claims.slot = tracked_slots.id INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+            WHERE player = ? AND tracked_worlds.archived = 0 ORDER BY tracked_worlds.name, tracked_slots.name",
+            player.id
+        )
+        .fetch_all(&bot.db)
+        .await
+        else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get claims")).await;
+            return;
+        };
+
+        let mut counted: BTreeMap<Option<i64>, Vec<ClaimedSlot>> = BTreeMap::new();
+        let mut free = vec![];
+        let mut finished = vec![];
+
+        for record in response {
+            let slot = ClaimedSlot {
+                slot: record.slot,
+                world: record.world,
+                status: record.status,
+                last_scrape: record.last_scrape,
+            };
+
+            if record.free > 0 {
+                free.push(slot);
+            } else if record.status >= Status::Goal.as_i64() {
+                finished.push(slot);
+            } else {
+                counted.entry(record.reality).or_default().push(slot);
+            }
+        }
+
+        let mut fields = vec![];
+        let mut joined_realities = 0;
+
+        for (reality, slots) in &counted {
+            let (name, allowance) = if let Some(reality) = reality {
+                let Some(reality) = Reality::fetch(bot, *reality).await else {
+                    let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get reality")).await;
+                    return;
+                };
+
+                if !reality.external {
+                    joined_realities += 1;
+                }
+
+                let Ok(granted_claims) = bot.granted_claims(player.id, reality.id).await else {
+                    let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get granted claims")).await;
+                    return;
+                };
+
+                (
+                    format!("{}{}", reality.name, if reality.external { " (external)" } else { "" }),
+                    reality.max_claims + reality.earned_claims(points) + granted_claims,
+                )
+            } else {
+                (String::from("No reality"), NO_REALITY_CLAIMS)
+            };
+
+            fields.push((format!("{name}: {}/{allowance} claims", slots.len()), join_lines(slots.iter().map(ClaimedSlot::line)), false));
+        }
+
+        fields.insert(
+            0,
+            (
+                String::from("Realities joined"),
+                format!("{joined_realities}/{MAX_REALITIES}\nExternal realities and claims outside of realities do not count towards this"),
+                false,
+            ),
+        );
+
+        if !counted.is_empty() {
+            fields.push((
+                String::from("Freeing a claim"),
+                String::from(
+                    "Every slot listed above counts towards your limits until it reaches its goal. Statuses are only updated when the tracker is scraped, so a slot you have finished may still count until then. Asking a host to mark it with `/done` frees the claim immediately",
+                ),
+                false,
+            ));
+        }
+
+        if !free.is_empty() {
+            fields.push((String::from("Free claims (not counted)"), join_lines(free.iter().map(ClaimedSlot::line)), false));
+        }
+
+        if !finished.is_empty() {
+            fields.push((String::from("Finished slots (not counted)"), join_lines(finished.iter().map(ClaimedSlot::line)), false));
+        }
+
+        let _ = command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().embed(
+                    CreateEmbed::new()
+                        .title("Your claims")
+                        .colour(Colour::DARK_PURPLE)
+                        .fields(fields)
+                        .footer(CreateEmbedFooter::new("Use /reality list to see the rules for each reality")),
+                ),
+            )
+            .await;
+    }
+}
+
+fn join_lines(lines: impl ExactSizeIterator<Item = String>) -> String {
+    let total = lines.len();
+    let mut value = String::new();
+
+    for (i, line) in lines.enumerate() {
+        if value.len() + line.len() + 1 > MAX_FIELD_LENGTH {
+            value.push_str(&format!("\n*and {} more*", total - i));
+            break;
+        }
+
+        if !value.is_empty() {
+            value.push('\n');
+        }
+        value.push_str(&line);
+    }
+
+    value
+}
@@ -1,7 +1,15 @@
-use serenity::all::{CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, EditInteractionResponse, ResolvedOption, ResolvedValue};
+use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand, EditInteractionResponse};
 use sqlx::query;
 
-use crate::{commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        name: String = ("name", "Name of the new world"),
+        max_claims: i64 = ("max-claims", "Maximum claims within reality"),
+        external: Option<bool> = ("external", "If reality is excluded from maximum reality claims. Defaults to false"),
+    }
+}
 
 pub struct NewRealityCommand {}
 
@@ -9,12 +17,7 @@ impl Command for NewRealityCommand {
     const NAME: &'static str = "new-reality";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Creates a new reality")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "name", "Name of the new world").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "max-claims", "Maximum claims within reality").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "external", "If reality is excluded from maximum reality claims. Defaults to false").required(false))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Creates a new reality").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -25,23 +28,11 @@ impl Command for NewRealityCommand {
             return;
         }
 
-        let mut name = "";
-        let mut max_claims = 0;
-        let mut external = false;
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("name", ResolvedValue::String(value)) => name = value,
-                ("max-claims", ResolvedValue::Integer(value)) => max_claims = value,
-                ("external", ResolvedValue::Boolean(value)) => external = value,
-                _ => (),
-            }
-        }
-
-        if name.is_empty() {
-            command.simple_reply(&ctx, "A world name is required").await;
+        let Some(Options { name, max_claims, external }) = Options::parse(&ctx, &command).await else {
             return;
-        }
+        };
+
+        let external = external.unwrap_or_default();
 
         if max_claims < 1 {
             command.simple_reply(&ctx, "Max claims must be a positive integer").await;
@@ -55,6 +46,8 @@ impl Command for NewRealityCommand {
             .await
             .is_ok()
         {
+            bot.audit(AuditEvent::new(command.user.id, Self::NAME, format!("Created reality {name} with a claim limit of {max_claims}")))
+                .await;
             let _ = command
                 .edit_response(
                     &ctx.http,
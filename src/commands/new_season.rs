@@ -0,0 +1,106 @@
+use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand, EditInteractionResponse};
+use sqlx::query;
+
+use crate::{audit::AuditEvent, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        name: String = ("name", "Name of the new season"),
+    }
+}
+
+pub struct NewSeasonCommand {}
+
+impl Command for NewSeasonCommand {
+    const NAME: &'static str = "new-season";
+
+    fn register() -> CreateCommand {
+        Options::add_options(
+            CreateCommand::new(Self::NAME)
+                .description("Ends the current season and starts a new one, resetting seasonal points")
+                .kind(CommandType::ChatInput),
+        )
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let user = command.user.id;
+
+        if !bot.admins.contains(&user) {
+            command.simple_reply(&ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(Options { name }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let Ok(mut transaction) = bot.db.begin().await else {
+            command.simple_reply(&ctx, "Failed to create transaction").await;
+            return;
+        };
+
+        let _ = command.defer_ephemeral(&ctx.http).await;
+
+        let previous = match query!("SELECT id, name FROM seasons ORDER BY id DESC LIMIT 1").fetch_optional(&mut *transaction).await {
+            Ok(response) => response,
+            Err(err) => {
+                println!("Failed to get current season: {err}");
+                let _ = transaction.rollback().await;
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get current season. Aborting")).await;
+                return;
+            }
+        };
+
+        if let Some(previous) = &previous {
+            if let Err(err) = query!(
+                "INSERT INTO season_standings (season, player, points) SELECT ?, id, season_points FROM players WHERE season_points > 0",
+                previous.id
+            )
+            .execute(&mut *transaction)
+            .await
+            {
+                println!("Failed to snapshot season standings: {err}");
+                let _ = transaction.rollback().await;
+                let _ = command
+                    .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to snapshot season standings. Aborting"))
+                    .await;
+                return;
+            }
+        }
+
+        if let Err(err) = query!("UPDATE players SET season_points = 0").execute(&mut *transaction).await {
+            println!("Failed to reset season points: {err}");
+            let _ = transaction.rollback().await;
+            let _ = command
+                .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to reset season points. Aborting"))
+                .await;
+            return;
+        }
+
+        if let Err(err) = query!("INSERT INTO seasons (name) VALUES (?)", name).execute(&mut *transaction).await {
+            println!("Failed to create season: {err}");
+            let _ = transaction.rollback().await;
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to create season. Aborting")).await;
+            return;
+        }
+
+        if let Err(err) = transaction.commit().await {
+            println!("Failed to commit transaction: {err}");
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to commit transaction. Aborting")).await;
+            return;
+        }
+
+        bot.audit(AuditEvent::new(user, Self::NAME, format!("Started season {name}"))).await;
+
+        let _ = command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(if let Some(previous) = previous {
+                    format!("Ended season {} and started season {name}", previous.name)
+                } else {
+                    format!("Started season {name}")
+                }),
+            )
+            .await;
+    }
+}
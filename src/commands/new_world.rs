@@ -1,13 +1,23 @@
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serenity::all::{
-    AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
-    EditInteractionResponse, ResolvedOption, ResolvedValue,
+    Attachment, AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse,
 };
 use sqlx::query;
 use tokio::{spawn, time::sleep};
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        name: String = ("name", "Name of the new world"),
+        preclaim_end: i64 = ("preclaim-end", "Time preclaims close, as UNIX timestamp"),
+        slot_file: Attachment = ("slot-file", "Output file from clean_yamls"),
+        reality: Option<String> = ("reality", "Name of the reality").set_autocomplete(true),
+        message: Option<String> = ("message", "Additional message to display"),
+        ping: Option<bool> = ("ping", "If @preclaims should be pinged. Defaults to true"),
+    }
+}
 
 pub struct NewWorldCommand {}
 
@@ -15,19 +25,7 @@ impl Command for NewWorldCommand {
     const NAME: &'static str = "new-world";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Creates a new world")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "name", "Name of the new world").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "preclaim-end", "Time preclaims close, as UNIX timestamp").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Attachment, "slot-file", "Output file from clean_yamls").required(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "reality", "Name of the reality")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "message", "Additional message to display").required(false))
-            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "ping", "If @preclaims should be pinged. Defaults to true").required(false))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Creates a new world").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -38,35 +36,25 @@ impl Command for NewWorldCommand {
             return;
         }
 
-        let mut name = "";
-        let mut reality_name = None;
-        let mut preclaim_end = 0;
-        let mut slot_file = None;
-        let mut message = None;
-        let mut ping = true;
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("name", ResolvedValue::String(value)) => name = value,
-                ("reality", ResolvedValue::String(value)) => reality_name = Some(value),
-                ("preclaim-end", ResolvedValue::Integer(value)) => preclaim_end = value,
-                ("slot-file", ResolvedValue::Attachment(value)) => slot_file = Some(value),
-                ("message", ResolvedValue::String(value)) => message = Some(value),
-                ("ping", ResolvedValue::Boolean(value)) => ping = value,
-                _ => (),
-            }
-        }
-
-        if name.is_empty() {
-            command.simple_reply(&ctx, "A world name is required").await;
+        let Some(Options {
+            name,
+            preclaim_end,
+            slot_file,
+            reality: reality_name,
+            message,
+            ping,
+        }) = Options::parse(&ctx, &command).await
+        else {
             return;
-        }
+        };
 
-        let reality = if let Some(reality_name) = reality_name {
-            if let Ok(response) = query!("SELECT id FROM realities WHERE name = ? LIMIT 1", reality_name).fetch_one(&bot.db).await {
+        let ping = ping.unwrap_or(true);
+
+        let reality = if let Some(reality_name) = &reality_name {
+            if let Ok(response) = query!("SELECT id FROM realities WHERE name = ? AND archived = 0 LIMIT 1", reality_name).fetch_one(&bot.db).await {
                 Some(response.id)
             } else {
-                command.simple_reply(&ctx, "Failed to get reality").await;
+                command.simple_reply(&ctx, "Failed to get reality. Archived realities cannot be used").await;
                 return;
             }
         } else {
@@ -81,26 +69,19 @@ impl Command for NewWorldCommand {
             return;
         }
 
-        let slot_file = if let Some(slot_file) = slot_file {
-            if slot_file.size > 1_000_000 {
-                let _ = command
-                    .create_response(
-                        &ctx.http,
-                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().ephemeral(true).content("Slot file is too large")),
-                    )
-                    .await;
-                return;
-            } else {
-                slot_file
-            }
-        } else {
-            command.simple_reply(&ctx, "Slot file does not exist").await;
+        if slot_file.size > 1_000_000 {
+            let _ = command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().ephemeral(true).content("Slot file is too large")),
+                )
+                .await;
             return;
-        };
+        }
 
         let _ = command.defer_ephemeral(&ctx.http).await;
 
-        if let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE id IN (SELECT world FROM tracked_slots WHERE status < 2)")
+        if let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE archived = 0 AND id IN (SELECT world FROM tracked_slots WHERE status < 2)")
             .fetch_all(&bot.db)
             .await
         {
@@ -130,17 +111,27 @@ impl Command for NewWorldCommand {
             .await
         {
             let slot_len = slots.len();
-            for (name, games, notes, points) in slots {
-                if query!("INSERT INTO slots (world, name, games, notes, points) VALUES (?, ?, ?, ?, ?)", response.id, name, games, notes, points)
-                    .execute(&bot.db)
-                    .await
-                    .is_err()
+            for (slot_name, games, notes, points) in slots {
+                if query!(
+                    "INSERT INTO slots (world, name, games, notes, points) VALUES (?, ?, ?, ?, ?)",
+                    response.id,
+                    slot_name,
+                    games,
+                    notes,
+                    points
+                )
+                .execute(&bot.db)
+                .await
+                .is_err()
                 {
+                    bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Failed to create slot").world(&name).slot(&slot_name)).await;
                     let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to create slots for new world")).await;
                     return;
                 }
             }
 
+            bot.audit(AuditEvent::new(command.user.id, Self::NAME, format!("Created world with {slot_len} slots")).world(&name))
+                .await;
             let _ = command
                 .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Successfully created world {name} with {slot_len} yamls")))
                 .await;
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use serenity::all::{Colour, CommandInteraction, CommandType, Context, CreateCommand, CreateEmbed, EditInteractionResponse, User};
+use sqlx::{query, query_as};
+
+use crate::{
+    commands::{track_world::split_game_str, Command},
+    options::command_options,
+    util::SimpleReply,
+    Bot, Player,
+};
+
+const FAVOURITE_GAMES: usize = 3;
+
+command_options! {
+    struct Options {
+        player: Option<User> = ("player", "Player to view the profile of, defaults to yourself"),
+    }
+}
+
+pub struct ProfileCommand {}
+
+impl Command for ProfileCommand {
+    const NAME: &'static str = "profile";
+
+    fn register() -> CreateCommand {
+        Options::add_options(CreateCommand::new(Self::NAME).description("View a player's claim and completion history").kind(CommandType::ChatInput))
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let Some(Options { player }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let user = player.as_ref().unwrap_or(&command.user);
+
+        // Viewing someone shouldn't create a player for them, so only look up existing players
+        let snowflake = i64::from(user.id);
+        let player = match query_as!(Player, "SELECT id, name, unspent_points FROM players WHERE snowflake = ? LIMIT 1", snowflake)
+            .fetch_optional(&bot.db)
+            .await
+        {
+            Ok(Some(player)) => player,
+            Ok(None) => {
+                command.simple_reply(&ctx, "No profile").await;
+                return;
+            }
+            Err(_) => {
+                command.simple_reply(&ctx, "Failed to get user").await;
+                return;
+            }
+        };
+
+        let _ = command.defer_ephemeral(&ctx.http).await;
+
+        let Ok(points) = query!(
+            "SELECT players.points, players.season_points, target.snowflake AS \"transfer_to?\" FROM players LEFT JOIN players AS target ON target.id = players.transfer_to WHERE players.id = ? LIMIT 1",
+            player.id
+        )
+        .fetch_one(&bot.db)
+        .await
+        else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get points")).await;
+            return;
+        };
+
+        let Ok(stats) = query!(
+            "SELECT COUNT(*) AS claimed,
+            COUNT(*) FILTER (WHERE archived = 1) AS finished,
+            COUNT(*) FILTER (WHERE archived = 1 AND status IN (2, 4)) AS completed,
+            AVG(goal_timestamp - claims.timestamp) FILTER (WHERE goal_timestamp IS NOT NULL AND claims.timestamp > 0 AND goal_timestamp >= claims.timestamp) AS \"time_to_goal?: f64\"
+            FROM claims INNER JOIN tracked_slots ON claims.slot = tracked_slots.id INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+            WHERE player = ?",
+            player.id
+        )
+        .fetch_one(&bot.db)
+        .await
+        else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get claim history")).await;
+            return;
+        };
+
+        let Ok(games_response) = query!("SELECT games FROM claims INNER JOIN tracked_slots ON claims.slot = tracked_slots.id WHERE player = ?", player.id)
+            .fetch_all(&bot.db)
+            .await
+        else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get claimed games")).await;
+            return;
+        };
+
+        let Ok(realities_response) = query!(
+            "SELECT realities.name FROM current_realities INNER JOIN realities ON realities.id = current_realities.reality WHERE player = ? ORDER BY realities.name",
+            player.id
+        )
+        .fetch_all(&bot.db)
+        .await
+        else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get current realities")).await;
+            return;
+        };
+
+        let mut games: HashMap<String, i64> = HashMap::new();
+        for record in &games_response {
+            for (game, count) in split_game_str(&record.games) {
+                *games.entry(game.to_owned()).or_default() += count;
+            }
+        }
+        let mut games: Vec<_> = games.into_iter().collect();
+        games.sort_by(|(a_game, a_count), (b_game, b_count)| b_count.cmp(a_count).then_with(|| a_game.cmp(b_game)));
+
+        let mut fields = vec![
+            (String::from("Slots claimed"), stats.claimed.to_string(), true),
+            (
+                String::from("Completion rate"),
+                if stats.finished > 0 {
+                    format!("{}% ({}/{})", stats.completed * 100 / stats.finished, stats.completed, stats.finished)
+                } else {
+                    String::from("*No finished slots*")
+                },
+                true,
+            ),
+            (
+                String::from("Average time to goal"),
+                if let Some(seconds) = stats.time_to_goal {
+                    let hours = seconds as i64 / 3600;
+                    format!("{} days, {} hours", hours / 24, hours % 24)
+                } else {
+                    String::from("*Unknown*")
+                },
+                true,
+            ),
+            (
+                String::from("Favourite games"),
+                if games.is_empty() {
+                    String::from("*None*")
+                } else {
+                    games.into_iter().take(FAVOURITE_GAMES).map(|(game, count)| format!("{game} ({count})")).collect::<Vec<_>>().join("\n")
+                },
+                false,
+            ),
+            (
+                String::from("Current realities"),
+                if realities_response.is_empty() {
+                    String::from("*None*")
+                } else {
+                    realities_response.into_iter().map(|record| record.name).collect::<Vec<_>>().join("\n")
+                },
+                false,
+            ),
+            (String::from("Points"), format!("{} all-time\n{} this season", points.points, points.season_points), true),
+        ];
+
+        if let Some(transfer_to) = points.transfer_to {
+            fields.push((String::from("Transferring points to"), format!("<@{transfer_to}>"), true));
+        }
+
+        let _ = command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().embed(CreateEmbed::new().title(player.name).colour(Colour::DARK_PURPLE).fields(fields)),
+            )
+            .await;
+    }
+}
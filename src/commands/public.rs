@@ -1,11 +1,77 @@
-use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use serde::{Deserialize, Serialize};
 use serenity::all::{
-    AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, CreateEmbed, EditInteractionResponse, ResolvedOption, ResolvedValue,
+    AutocompleteOption, ButtonStyle, Colour, CommandInteraction, CommandType, ComponentInteraction, Context, CreateActionRow, CreateButton, CreateCommand, CreateEmbed, CreateEmbedFooter,
+    CreateInteractionResponse, CreateMessage, EditInteractionResponse, ModalInteraction, ResolvedOption, ResolvedValue, UserId,
 };
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, Bot};
+use crate::{
+    audit::AuditEvent,
+    autocomplete::Autocomplete,
+    commands::{track_world::split_game_str, Command},
+    custom_id::{CustomId, Fields},
+    discord::Discord,
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
+    util::SimpleReply,
+    Bot,
+};
+
+const DEFAULT_EXPIRY_HOURS: i64 = 72;
+const MAX_EXPIRY_HOURS: i64 = 30 * 24;
+const MAX_DESCRIPTION_LENGTH: usize = 600;
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Other,
+    AsyncHelp,
+    ItemHunt,
+    Spectators,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Filter {
+    world: Option<String>,
+    game: Option<String>,
+    kind: Option<i64>,
+}
+
+pub struct Board {
+    filter: String,
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone)]
+pub struct Entry {
+    id: i64,
+    world: String,
+    slot: String,
+    games: String,
+    owner: i64,
+    description: String,
+    kind: Kind,
+    expires: Option<i64>,
+}
+
+command_options! {
+    struct Options {
+        world: Option<String> = ("world", "Name of the world").set_autocomplete(true),
+        slot: Option<String> = ("slot", "Name of the slot to mark as public").set_autocomplete(true),
+        description: Option<String> = ("description", "A description, recommended to specify which games in the slot are public"),
+        kind: Option<String> = ("kind", "What kind of help is wanted, or only show public slots of this kind")
+            .add_string_choice("Async help", "async-help")
+            .add_string_choice("Item hunt", "item-hunt")
+            .add_string_choice("Spectators welcome", "spectators")
+            .add_string_choice("Other", "other"),
+        expires_in: Option<i64> = ("expires-in", "Hours until the slot is no longer public. Defaults to 72")
+            .min_int_value(1)
+            .max_int_value(MAX_EXPIRY_HOURS as u64),
+        remove: Option<bool> = ("remove", "Stop listing the slot as public"),
+        game: Option<String> = ("game", "Only show public slots with this game").set_autocomplete(true),
+    }
+}
 
 pub struct PublicCommand {}
 
@@ -13,127 +79,147 @@ impl Command for PublicCommand {
     const NAME: &'static str = "public";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("View public slots or mark a slot of yours as public")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(false).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot", "Name of the slot").required(false).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(
-                    CommandOptionType::String,
-                    "description",
-                    "A description, recommended to include game and specify which games in the slot are public",
-                )
-                .required(false),
-            )
+        Options::add_options(
+            CreateCommand::new(Self::NAME)
+                .description("View public slots or mark a slot of yours as public")
+                .kind(CommandType::ChatInput),
+        )
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut world = "";
-        let mut slot = "";
-        let mut description = "";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("slot", ResolvedValue::String(value)) => slot = value,
-                ("description", ResolvedValue::String(value)) => description = value,
-                _ => (),
+        let Some(Options {
+            world,
+            slot,
+            description,
+            kind,
+            expires_in,
+            remove,
+            game,
+        }) = Options::parse(&ctx, &command).await
+        else {
+            return;
+        };
+
+        let expires_in = expires_in.unwrap_or(DEFAULT_EXPIRY_HOURS);
+        let remove = remove.unwrap_or_default();
+
+        let kind = match kind.as_deref().map(Kind::try_from) {
+            Some(Some(kind)) => Some(kind),
+            Some(None) => {
+                command.simple_reply(&ctx, "Invalid kind").await;
+                return;
             }
-        }
+            None => None,
+        };
 
-        let _ = command.defer_ephemeral(&ctx.http).await;
+        let Some(slot) = slot else {
+            let kind = kind.map(Kind::as_i64);
 
-        if world.is_empty() {
-            if let Ok(response) = query!("SELECT world, slot, description FROM public_claims").fetch_all(&bot.db).await {
-                if response.is_empty() {
-                    let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("No worlds have public claims")).await;
-                } else {
-                    let mut worlds: HashMap<String, Vec<(String, String)>> = HashMap::new();
-
-                    for record in response {
-                        if let Some(description) = record.description {
-                            worlds
-                                .entry(record.world)
-                                .and_modify(|entry| entry.push((record.slot.to_owned(), description.to_owned())))
-                                .or_insert(vec![(record.slot, description)]);
-                        }
-                    }
+            let Some(filter) = bot.save_filter(Self::NAME, &Filter { world, game, kind }).await else {
+                command.simple_reply(&ctx, "Failed to save public slot filter").await;
+                return;
+            };
 
-                    let _ = command
-                        .edit_response(
-                            &ctx.http,
-                            EditInteractionResponse::new().embed(CreateEmbed::new().fields(worlds.into_iter().map(|(world, slots)| {
-                                (
-                                    world,
-                                    slots.into_iter().map(|(name, description)| format!("**{name}**: {description}")).collect::<Vec<_>>().join("\n"),
-                                    false,
-                                )
-                            }))),
-                        )
-                        .await;
-                }
-            } else {
-                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get public claims")).await;
-            }
+            let Some(response) = Self::first_page(bot, filter).await else {
+                command.simple_reply(&ctx, "No public slots match this filter").await;
+                return;
+            };
 
+            let _ = command.create_response(&ctx.http, CreateInteractionResponse::Message(response.into())).await;
             return;
-        }
-
-        if slot.is_empty() {
-            if let Ok(response) = query!("SELECT slot, description FROM public_claims WHERE world = ?", world).fetch_all(&bot.db).await {
-                if response.is_empty() {
-                    let _ = command
-                        .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("{world} does not have public claims")))
-                        .await;
-                } else {
-                    let _ = command
-                        .edit_response(
-                            &ctx.http,
-                            EditInteractionResponse::new().embed(
-                                CreateEmbed::new().field(
-                                    world,
-                                    response
-                                        .into_iter()
-                                        .filter_map(|record| record.description.map(|description| format!("**{}**: {description}", record.slot)))
-                                        .collect::<Vec<_>>()
-                                        .join("\n"),
-                                    false,
-                                ),
-                            ),
-                        )
-                        .await;
-                }
-            } else {
-                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get public claims")).await;
-            }
+        };
 
+        let Some(world) = world else {
+            command.simple_reply(&ctx, "A world is required to mark a slot as public").await;
             return;
-        }
+        };
 
-        if description.is_empty() {
-            description = "*No description provided*";
-        }
+        let _ = command.defer_ephemeral(&ctx.http).await;
 
         let user = i64::from(command.user.id);
-        if let Ok(response) = query!(
-            "UPDATE claims SET public = ? WHERE player IN (SELECT id FROM players WHERE snowflake = ?) AND slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ?))",
-            description,
+        let Ok(Some(claim)) = query!(
+            "SELECT claims.id FROM claims INNER JOIN players ON claims.player = players.id WHERE snowflake = ?
+            AND slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0)) LIMIT 1",
             user,
             slot,
             world
         )
-        .execute(&bot.db)
+        .fetch_optional(&bot.db)
         .await
-        {
-            if response.rows_affected() == 0 {
-                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to mark claim as public")).await;
-            } else {
-                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Successfully marked claim as public")).await;
+        else {
+            let _ = command
+                .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("You do not have a claim on {slot} in {world}")))
+                .await;
+            return;
+        };
+
+        if remove {
+            if query!("UPDATE claims SET public = NULL, public_expires = NULL WHERE id = ?", claim.id).execute(&bot.db).await.is_err() {
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to remove public claim")).await;
+                return;
             }
-        } else {
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to mark claim as public")).await;
+
+            bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Removed public claim").world(&world).slot(&slot)).await;
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Your slot is no longer public")).await;
+            return;
+        }
+
+        let description = description.unwrap_or_else(|| String::from("*No description provided*"));
+
+        if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            let _ = command
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new().content(format!("Descriptions can be at most {MAX_DESCRIPTION_LENGTH} characters long")),
+                )
+                .await;
+            return;
+        }
+
+        let kind = kind.unwrap_or(Kind::Other);
+        let kind_i = kind.as_i64();
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let expires = current_time.as_secs() as i64 + expires_in * 60 * 60;
+
+        let Ok(mut transaction) = bot.db.begin().await else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to create transaction")).await;
+            return;
+        };
+
+        if let Err(err) = query!("UPDATE claims SET public = ?, public_kind = ?, public_expires = ? WHERE id = ?", description, kind_i, expires, claim.id)
+            .execute(&mut *transaction)
+            .await
+        {
+            println!("Failed to mark claim as public: {err}");
+            let _ = transaction.rollback().await;
+            let _ = command
+                .edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to mark claim as public. Aborting"))
+                .await;
+            return;
         }
+
+        if let Err(err) = query!("DELETE FROM public_volunteers WHERE claim = ?", claim.id).execute(&mut *transaction).await {
+            println!("Failed to clear volunteers: {err}");
+            let _ = transaction.rollback().await;
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to clear volunteers. Aborting")).await;
+            return;
+        }
+
+        if let Err(err) = transaction.commit().await {
+            println!("Failed to commit transaction: {err}");
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to commit transaction. Aborting")).await;
+            return;
+        }
+
+        bot.audit(
+            AuditEvent::new(command.user.id, Self::NAME, format!("Marked claim as public ({})", kind.as_str()))
+                .world(&world)
+                .slot(&slot),
+        )
+        .await;
+        let _ = command
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Successfully marked claim as public until <t:{expires}:f>")))
+            .await;
     }
 
     async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
@@ -149,9 +235,250 @@ impl Command for PublicCommand {
 
                 bot.autocomplete_slots(ctx, &interaction, value, world).await;
             }
+            Some(AutocompleteOption { name: "game", value, .. }) => bot.autocomplete_games(ctx, &interaction, value, true).await,
             Some(_) | None => {
                 interaction.no_autocomplete(&ctx).await;
             }
         }
     }
 }
+
+impl Kind {
+    fn try_from(value: &str) -> Option<Self> {
+        match value {
+            "other" => Some(Kind::Other),
+            "async-help" => Some(Kind::AsyncHelp),
+            "item-hunt" => Some(Kind::ItemHunt),
+            "spectators" => Some(Kind::Spectators),
+            _ => None,
+        }
+    }
+
+    fn as_i64(self) -> i64 {
+        match self {
+            Kind::Other => 0,
+            Kind::AsyncHelp => 1,
+            Kind::ItemHunt => 2,
+            Kind::Spectators => 3,
+        }
+    }
+
+    fn from_i64(value: i64) -> Self {
+        match value {
+            1 => Kind::AsyncHelp,
+            2 => Kind::ItemHunt,
+            3 => Kind::Spectators,
+            _ => Kind::Other,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Other => "Other",
+            Kind::AsyncHelp => "Async help",
+            Kind::ItemHunt => "Item hunt",
+            Kind::Spectators => "Spectators welcome",
+        }
+    }
+}
+
+impl Paginate<Board, Entry, Entry, i64> for PublicCommand {
+    const PAGE_SIZE: usize = 5;
+
+    async fn get_containers(bot: &Bot, filter_id: i64, _view: View) -> Vec<Board> {
+        let Some(filter) = bot.saved_filter::<Filter>(Self::NAME, filter_id).await else {
+            return vec![];
+        };
+
+        let Ok(response) = query!(
+            "SELECT id AS \"id!\", world, slot, games, owner, description AS \"description!\", kind, expires FROM public_claims WHERE (?1 IS NULL OR world = ?1) AND (?2 IS NULL OR kind = ?2)",
+            filter.world,
+            filter.kind
+        )
+        .fetch_all(&bot.db)
+        .await
+        else {
+            return vec![];
+        };
+
+        let game = filter.game.as_deref().map(str::to_lowercase);
+        let entries: Vec<_> = response
+            .into_iter()
+            .filter(|record| game.as_ref().is_none_or(|game| split_game_str(&record.games).any(|(name, _)| name.to_lowercase().contains(game))))
+            .map(|record| Entry {
+                id: record.id,
+                world: record.world,
+                slot: record.slot,
+                games: record.games,
+                owner: record.owner,
+                description: record.description,
+                kind: Kind::from_i64(record.kind),
+                expires: record.expires,
+            })
+            .collect();
+
+        if entries.is_empty() {
+            return vec![];
+        }
+
+        let mut description = vec![];
+        if let Some(world) = filter.world {
+            description.push(format!("World: {world}"));
+        }
+        if let Some(game) = filter.game {
+            description.push(format!("Game: {game}"));
+        }
+        if let Some(kind) = filter.kind {
+            description.push(format!("Kind: {}", Kind::from_i64(kind).as_str()));
+        }
+
+        vec![Board {
+            filter: description.join("\n"),
+            entries,
+        }]
+    }
+
+    fn id_prefix(filter_id: i64) -> CustomId {
+        CustomId::new(Self::NAME).push(filter_id)
+    }
+
+    fn additional_components(components: &mut Vec<CreateActionRow>, _container: &Board, details: &[Entry], _page: usize, _view: View) {
+        if details.is_empty() {
+            return;
+        }
+
+        components.push(CreateActionRow::Buttons(
+            details
+                .iter()
+                .map(|entry| {
+                    CreateButton::new(CustomId::new(Self::NAME).push("volunteer").push(entry.id).build())
+                        .style(ButtonStyle::Primary)
+                        .label(format!("Volunteer: {}", entry.slot.chars().take(60).collect::<String>()))
+                })
+                .collect(),
+        ));
+    }
+}
+
+impl PageContainer<Entry, Entry, i64> for Board {
+    fn items(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    fn page_setup(&self) -> CreateEmbed {
+        let embed = CreateEmbed::new().title("Public slots").colour(Colour::DARK_PURPLE);
+
+        if self.filter.is_empty() { embed } else { embed.description(&self.filter) }.footer(CreateEmbedFooter::new(format!("{} public slots", self.entries.len())))
+    }
+}
+
+impl PageItem<Entry, i64> for Entry {
+    async fn details(&self, _bot: &Bot, _extra: i64) -> Option<Entry> {
+        Some(self.clone())
+    }
+}
+
+impl PageDetails for Entry {
+    fn field(&self) -> (String, String, bool) {
+        let mut lines = vec![format!("**{}** by <@{}>", self.kind.as_str(), self.owner), format!("**Games**: {}", self.games)];
+
+        if let Some(expires) = self.expires {
+            lines.push(format!("**Expires**: <t:{expires}:R>"));
+        }
+
+        lines.push(self.description.chars().take(MAX_DESCRIPTION_LENGTH).collect());
+
+        (format!("{} in {}", self.slot, self.world), lines.join("\n"), false)
+    }
+}
+
+impl PublicCommand {
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, mut fields: Fields<'_>) {
+        if fields.tag("volunteer") {
+            let Some(claim) = fields.next() else {
+                interaction.simple_reply(&ctx, "Malformed public slot").await;
+                return;
+            };
+
+            Self::volunteer(bot, &ctx, interaction, claim).await;
+            return;
+        }
+
+        let Some(filter_id) = fields.next() else {
+            interaction.simple_reply(&ctx, "Malformed public slot filter").await;
+            return;
+        };
+
+        if !Self::try_handle_interaction(bot, &ctx, interaction, fields, filter_id).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    async fn volunteer(bot: &Bot, ctx: &Context, interaction: &ComponentInteraction, claim: i64) {
+        let Ok(Some(entry)) = query!("SELECT world, slot, owner, kind FROM public_claims WHERE id = ? LIMIT 1", claim).fetch_optional(&bot.db).await else {
+            interaction.simple_reply(ctx, "This slot is no longer public").await;
+            return;
+        };
+
+        let user = &interaction.user;
+        if entry.owner == i64::from(user.id) {
+            interaction.simple_reply(ctx, "You cannot volunteer for your own slot").await;
+            return;
+        }
+
+        let Some(player) = bot.get_player(i64::from(user.id), &user.name).await else {
+            interaction.simple_reply(ctx, "Failed to get user").await;
+            return;
+        };
+
+        match query!("INSERT OR IGNORE INTO public_volunteers (claim, player) VALUES (?, ?)", claim, player.id).execute(&bot.db).await {
+            Ok(response) if response.rows_affected() == 0 => {
+                interaction.simple_reply(ctx, "You have already volunteered for this slot").await;
+                return;
+            }
+            Ok(_) => (),
+            Err(err) => {
+                println!("Failed to record volunteer: {err}");
+                interaction.simple_reply(ctx, "Failed to record volunteer").await;
+                return;
+            }
+        }
+
+        let kind = Kind::from_i64(entry.kind);
+        let message = CreateMessage::new().content(format!(
+            "<@{}> volunteered for your public slot **{}** in {} ({}). Reach out to them to coordinate.",
+            user.id,
+            entry.slot,
+            entry.world,
+            kind.as_str()
+        ));
+
+        if ctx.direct_message(UserId::new(entry.owner as u64), message).await.is_err() {
+            let _ = query!("DELETE FROM public_volunteers WHERE claim = ? AND player = ?", claim, player.id).execute(&bot.db).await;
+            interaction
+                .simple_reply(ctx, format!("Failed to message <@{}>, they may have direct messages disabled", entry.owner))
+                .await;
+            return;
+        }
+
+        bot.audit(
+            AuditEvent::new(user.id, Self::NAME, format!("Volunteered for public slot ({})", kind.as_str()))
+                .world(&entry.world)
+                .slot(&entry.slot)
+                .target(entry.owner),
+        )
+        .await;
+        interaction.simple_reply(ctx, format!("<@{}> has been notified that you volunteered", entry.owner)).await;
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, mut fields: Fields<'_>) {
+        let Some(filter_id) = fields.next() else {
+            interaction.simple_reply(&ctx, "Malformed public slot filter").await;
+            return;
+        };
+
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, filter_id).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+}
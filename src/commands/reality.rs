@@ -0,0 +1,429 @@
+use serenity::all::{
+    AutocompleteOption, Colour, CommandInteraction, CommandOptionType, CommandType, ComponentInteraction, Context, CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    EditInteractionResponse, ModalInteraction, ResolvedOption, ResolvedValue,
+};
+use sqlx::query;
+
+use crate::{
+    audit::AuditEvent,
+    autocomplete::Autocomplete,
+    commands::Command,
+    custom_id::Fields,
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
+    util::SimpleReply,
+    Bot,
+};
+
+const MAX_FIELD_LENGTH: usize = 1000;
+
+pub struct Realities {
+    realities: Vec<RealityId>,
+}
+
+pub struct RealityId(i64);
+
+pub struct Reality {
+    name: String,
+    max_claims: i64,
+    external: bool,
+    archived: bool,
+    min_completed: i64,
+    unclaim_cooldown: i64,
+    points_per_claim: i64,
+    game_caps: Vec<(String, i64)>,
+    claims: Vec<(i64, i64)>,
+}
+
+command_options! {
+    struct EditOptions {
+        reality: String = ("reality", "Name of the reality").set_autocomplete(true),
+        name: Option<String> = ("name", "New name of the reality"),
+        max_claims: Option<i64> = ("max-claims", "Maximum claims within reality"),
+        external: Option<bool> = ("external", "If reality is excluded from maximum reality claims"),
+        min_completed: Option<i64> = ("min-completed", "Slots a player must complete before holding a second claim. 0 to disable"),
+        unclaim_cooldown: Option<i64> = ("unclaim-cooldown", "Hours a player must wait to claim again after being unclaimed. 0 to disable"),
+        points_per_claim: Option<i64> = ("points-per-claim", "Points needed to earn each additional claim. 0 to disable"),
+    }
+}
+
+command_options! {
+    struct GameCapOptions {
+        reality: String = ("reality", "Name of the reality").set_autocomplete(true),
+        game: String = ("game", "Name of the game"),
+        max_claims: i64 = ("max-claims", "Maximum slots of the game per player. 0 to remove the limit"),
+    }
+}
+
+command_options! {
+    struct ArchiveOptions {
+        reality: String = ("reality", "Name of the reality").set_autocomplete(true),
+        restore: Option<bool> = ("restore", "Restores an archived reality instead. Defaults to false"),
+    }
+}
+
+pub struct RealityCommand {}
+
+impl Command for RealityCommand {
+    const NAME: &'static str = "reality";
+
+    fn register() -> CreateCommand {
+        CreateCommand::new(Self::NAME)
+            .description("Manage realities")
+            .kind(CommandType::ChatInput)
+            .add_option(EditOptions::add_sub_options(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "edit",
+                "Changes the name or rules of a reality",
+            )))
+            .add_option(GameCapOptions::add_sub_options(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "game-cap",
+                "Limits how many slots of a game a player can hold at once in a reality",
+            )))
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Lists realities, their rules and current claims"))
+            .add_option(ArchiveOptions::add_sub_options(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "archive",
+                "Archives a reality so it can no longer be used for new worlds",
+            )))
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
+            match (option_name, value) {
+                ("edit", ResolvedValue::SubCommand(options)) => Self::edit(bot, &ctx, &command, options).await,
+                ("list", ResolvedValue::SubCommand(_)) => Self::list(bot, &ctx, &command).await,
+                ("archive", ResolvedValue::SubCommand(options)) => Self::archive(bot, &ctx, &command, options).await,
+                ("game-cap", ResolvedValue::SubCommand(options)) => Self::game_cap(bot, &ctx, &command, options).await,
+                _ => command.simple_reply(&ctx, "Unrecognized subcommand").await,
+            }
+        }
+    }
+
+    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
+        match interaction.data.autocomplete() {
+            Some(AutocompleteOption { name: "reality", value, .. }) => bot.autocomplete_all_realities(ctx, &interaction, value).await,
+            Some(_) | None => {
+                interaction.no_autocomplete(&ctx).await;
+            }
+        }
+    }
+}
+
+impl RealityCommand {
+    async fn edit(bot: &Bot, ctx: &Context, command: &CommandInteraction, options: Vec<ResolvedOption<'_>>) {
+        if !bot.admins.contains(&command.user.id) {
+            command.simple_reply(ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(EditOptions {
+            reality,
+            name,
+            max_claims,
+            external,
+            min_completed,
+            unclaim_cooldown,
+            points_per_claim,
+        }) = EditOptions::parse_from(ctx, command, &options).await
+        else {
+            return;
+        };
+
+        if name.is_none() && max_claims.is_none() && external.is_none() && min_completed.is_none() && unclaim_cooldown.is_none() && points_per_claim.is_none() {
+            command.simple_reply(ctx, "Nothing to change").await;
+            return;
+        }
+
+        if max_claims.is_some_and(|max_claims| max_claims < 1) {
+            command.simple_reply(ctx, "Max claims must be a positive integer").await;
+            return;
+        }
+
+        if [min_completed, unclaim_cooldown, points_per_claim].into_iter().flatten().any(|value| value < 0) {
+            command.simple_reply(ctx, "Claim rules cannot be negative").await;
+            return;
+        }
+
+        if let Some(name) = &name {
+            match query!("SELECT id FROM realities WHERE name = ? AND name != ? LIMIT 1", name, reality).fetch_optional(&bot.db).await {
+                Ok(Some(_)) => {
+                    command.simple_reply(ctx, format!("A reality named {name} already exists")).await;
+                    return;
+                }
+                Ok(None) => (),
+                Err(_) => {
+                    command.simple_reply(ctx, "Failed to check for existing realities").await;
+                    return;
+                }
+            }
+        }
+
+        let _ = command.defer_ephemeral(&ctx.http).await;
+
+        match query!(
+            "UPDATE realities SET name = COALESCE(?, name), max_claims = COALESCE(?, max_claims), external = COALESCE(?, external), min_completed = COALESCE(?, min_completed),
+            unclaim_cooldown = COALESCE(? * 3600, unclaim_cooldown), points_per_claim = COALESCE(?, points_per_claim) WHERE name = ?",
+            name,
+            max_claims,
+            external,
+            min_completed,
+            unclaim_cooldown,
+            points_per_claim,
+            reality
+        )
+        .execute(&bot.db)
+        .await
+        {
+            Ok(response) if response.rows_affected() == 0 => {
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to find reality")).await;
+            }
+            Ok(_) => {
+                let mut changes = vec![];
+                if let Some(name) = name {
+                    changes.push(format!("renamed to {name}"));
+                }
+                if let Some(max_claims) = max_claims {
+                    changes.push(format!("claim limit set to {max_claims}"));
+                }
+                if let Some(external) = external {
+                    changes.push(String::from(if external { "made external" } else { "made internal" }));
+                }
+                if let Some(min_completed) = min_completed {
+                    changes.push(format!("completed slots needed for a second claim set to {min_completed}"));
+                }
+                if let Some(unclaim_cooldown) = unclaim_cooldown {
+                    changes.push(format!("unclaim cooldown set to {unclaim_cooldown} hours"));
+                }
+                if let Some(points_per_claim) = points_per_claim {
+                    changes.push(format!("points per additional claim set to {points_per_claim}"));
+                }
+                let changes = changes.join(", ");
+
+                bot.audit(AuditEvent::new(command.user.id, Self::NAME, format!("Edited reality {reality}: {changes}"))).await;
+                bot.push_needed().await;
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Reality {reality} {changes}"))).await;
+            }
+            Err(err) => {
+                println!("Failed to edit reality: {err}");
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to edit reality")).await;
+            }
+        }
+    }
+
+    async fn list(bot: &Bot, ctx: &Context, command: &CommandInteraction) {
+        let Some(response) = Self::first_page(bot, ()).await else {
+            command.simple_reply(ctx, "There are no realities").await;
+            return;
+        };
+
+        let _ = command.create_response(&ctx.http, CreateInteractionResponse::Message(response.into())).await;
+    }
+
+    async fn archive(bot: &Bot, ctx: &Context, command: &CommandInteraction, options: Vec<ResolvedOption<'_>>) {
+        if !bot.admins.contains(&command.user.id) {
+            command.simple_reply(ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(ArchiveOptions { reality, restore }) = ArchiveOptions::parse_from(ctx, command, &options).await else {
+            return;
+        };
+
+        let restore = restore.unwrap_or_default();
+
+        let archived = !restore;
+        match query!("UPDATE realities SET archived = ? WHERE name = ? AND archived != ?", archived, reality, archived)
+            .execute(&bot.db)
+            .await
+        {
+            Ok(response) if response.rows_affected() == 0 => {
+                command
+                    .simple_reply(
+                        ctx,
+                        if restore {
+                            "Failed to find an archived reality with that name"
+                        } else {
+                            "Failed to find an active reality with that name"
+                        },
+                    )
+                    .await;
+            }
+            Ok(_) => {
+                let outcome = if restore { format!("Restored reality {reality}") } else { format!("Archived reality {reality}") };
+                bot.audit(AuditEvent::new(command.user.id, Self::NAME, &outcome)).await;
+                command.simple_reply(ctx, outcome).await;
+            }
+            Err(err) => {
+                println!("Failed to archive reality: {err}");
+                command.simple_reply(ctx, "Failed to archive reality").await;
+            }
+        }
+    }
+
+    async fn game_cap(bot: &Bot, ctx: &Context, command: &CommandInteraction, options: Vec<ResolvedOption<'_>>) {
+        if !bot.admins.contains(&command.user.id) {
+            command.simple_reply(ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(GameCapOptions { reality, game, max_claims }) = GameCapOptions::parse_from(ctx, command, &options).await else {
+            return;
+        };
+
+        if max_claims < 0 {
+            command.simple_reply(ctx, "Max claims cannot be negative").await;
+            return;
+        }
+
+        let Ok(Some(reality_id)) = query!("SELECT id FROM realities WHERE name = ? LIMIT 1", reality)
+            .fetch_optional(&bot.db)
+            .await
+            .map(|response| response.map(|record| record.id))
+        else {
+            command.simple_reply(ctx, "Failed to find reality").await;
+            return;
+        };
+
+        let result = if max_claims == 0 {
+            query!("DELETE FROM reality_game_caps WHERE reality = ? AND game = ?", reality_id, game).execute(&bot.db).await
+        } else {
+            query!(
+                "INSERT INTO reality_game_caps (reality, game, max_claims) VALUES (?, ?, ?) ON CONFLICT (reality, game) DO UPDATE SET max_claims = excluded.max_claims",
+                reality_id,
+                game,
+                max_claims
+            )
+            .execute(&bot.db)
+            .await
+        };
+
+        if let Err(err) = result {
+            println!("Failed to set game cap: {err}");
+            command.simple_reply(ctx, "Failed to set game limit").await;
+            return;
+        }
+
+        let outcome = if max_claims == 0 {
+            format!("Removed the limit on {game} slots in {reality}")
+        } else {
+            format!("Limited {game} to {max_claims} slots per player in {reality}")
+        };
+        bot.audit(AuditEvent::new(command.user.id, Self::NAME, &outcome)).await;
+        command.simple_reply(ctx, outcome).await;
+    }
+
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, fields: Fields<'_>) {
+        if !Self::try_handle_interaction(bot, &ctx, interaction, fields, ()).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, fields: Fields<'_>) {
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, ()).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+}
+
+impl Paginate<Realities, RealityId, Reality> for RealityCommand {
+    const PAGE_SIZE: usize = 5;
+
+    async fn get_containers(bot: &Bot, _: (), _view: View) -> Vec<Realities> {
+        let Ok(response) = query!("SELECT id FROM realities ORDER BY archived ASC, name ASC").fetch_all(&bot.db).await else {
+            return vec![];
+        };
+
+        if response.is_empty() {
+            return vec![];
+        }
+
+        vec![Realities {
+            realities: response.into_iter().map(|record| RealityId(record.id)).collect(),
+        }]
+    }
+}
+
+impl PageContainer<RealityId, Reality> for Realities {
+    fn items(&self) -> &[RealityId] {
+        &self.realities
+    }
+
+    fn page_setup(&self) -> CreateEmbed {
+        CreateEmbed::new().title("Realities").colour(Colour::DARK_PURPLE)
+    }
+}
+
+impl PageItem<Reality> for RealityId {
+    async fn details(&self, bot: &Bot, _extra: ()) -> Option<Reality> {
+        let reality = query!(
+            "SELECT name, max_claims, external, archived, min_completed, unclaim_cooldown, points_per_claim FROM realities WHERE id = ? LIMIT 1",
+            self.0
+        )
+        .fetch_one(&bot.db)
+        .await
+        .ok()?;
+
+        let claims = query!(
+            "SELECT snowflake, claims FROM current_claims INNER JOIN players ON players.id = current_claims.player WHERE reality = ? ORDER BY claims DESC, name ASC",
+            self.0
+        )
+        .fetch_all(&bot.db)
+        .await
+        .ok()?;
+
+        let game_caps = query!("SELECT game, max_claims FROM reality_game_caps WHERE reality = ? ORDER BY game ASC", self.0)
+            .fetch_all(&bot.db)
+            .await
+            .ok()?;
+
+        Some(Reality {
+            name: reality.name,
+            max_claims: reality.max_claims,
+            external: reality.external > 0,
+            archived: reality.archived > 0,
+            min_completed: reality.min_completed,
+            unclaim_cooldown: reality.unclaim_cooldown,
+            points_per_claim: reality.points_per_claim,
+            game_caps: game_caps.into_iter().map(|record| (record.game, record.max_claims)).collect(),
+            claims: claims.into_iter().map(|record| (record.snowflake, record.claims)).collect(),
+        })
+    }
+}
+
+impl PageDetails for Reality {
+    fn field(&self) -> (String, String, bool) {
+        let mut value = format!("**Claim limit**: {}{}", self.max_claims, if self.external { "\n**External**" } else { "" });
+
+        if self.points_per_claim > 0 {
+            value.push_str(&format!("\n**Extra claims**: 1 per {} points", self.points_per_claim));
+        }
+        if self.min_completed > 0 {
+            value.push_str(&format!("\n**Second claim after**: {} completed slots", self.min_completed));
+        }
+        if self.unclaim_cooldown > 0 {
+            value.push_str(&format!("\n**Unclaim cooldown**: {} hours", self.unclaim_cooldown / 3600));
+        }
+        for (game, max_claims) in &self.game_caps {
+            value.push_str(&format!("\n**{game}**: {max_claims} per player"));
+        }
+
+        if self.claims.is_empty() {
+            value.push_str("\n*No current claims*");
+        } else {
+            for (i, (snowflake, claims)) in self.claims.iter().enumerate() {
+                let line = format!("\n<@{snowflake}>: {claims}/{}", self.max_claims);
+
+                if value.len() + line.len() > MAX_FIELD_LENGTH {
+                    value.push_str(&format!("\n*and {} more*", self.claims.len() - i));
+                    break;
+                }
+
+                value.push_str(&line);
+            }
+        }
+
+        (format!("{}{}", self.name, if self.archived { " (archived)" } else { "" }), value, false)
+    }
+}
@@ -1,18 +1,26 @@
-use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand};
+use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand, EditInteractionResponse, GuildId};
 
 use crate::{
+    audit::AuditEvent,
     commands::{register_all, Command},
+    options::command_options,
     util::SimpleReply,
     Bot,
 };
 
+command_options! {
+    struct Options {
+        guild: Option<GuildId> = ("guild", "ID of a guild to register to instead of globally, for testing"),
+    }
+}
+
 pub struct RegisterCommandsCommand {}
 
 impl Command for RegisterCommandsCommand {
     const NAME: &'static str = "register-commands";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME).description("Registers all commands").kind(CommandType::ChatInput)
+        Options::add_options(CreateCommand::new(Self::NAME).description("Registers all commands").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -23,10 +31,39 @@ impl Command for RegisterCommandsCommand {
             return;
         }
 
-        if let Err(err) = register_all(&ctx).await {
-            command.simple_reply(&ctx, format!("Failed to register commands with error {err:?}")).await;
-        } else {
-            command.simple_reply(&ctx, "Successfully registered commands").await;
+        let Some(Options { guild }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let _ = command.defer_ephemeral(&ctx.http).await;
+
+        let target = if let Some(guild) = guild { format!("guild {guild}") } else { String::from("globally") };
+
+        let diff = match register_all(&ctx, guild).await {
+            Ok(diff) => diff,
+            Err(err) => {
+                let _ = command
+                    .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Failed to register commands {target} with error {err:?}")))
+                    .await;
+                return;
+            }
+        };
+
+        let mut lines = vec![format!("Registered commands {target}")];
+        for (label, names) in [("Created", &diff.created), ("Updated", &diff.updated), ("Deleted", &diff.deleted)] {
+            if !names.is_empty() {
+                lines.push(format!("**{label}**: {}", names.iter().map(|name| format!("`/{name}`")).collect::<Vec<_>>().join(", ")));
+            }
         }
+        lines.push(format!("**Unchanged**: {}", diff.unchanged));
+
+        bot.audit(AuditEvent::new(
+            user,
+            Self::NAME,
+            format!("Registered {target}: {} created, {} updated, {} deleted", diff.created.len(), diff.updated.len(), diff.deleted.len()),
+        ))
+        .await;
+
+        let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content(lines.join("\n"))).await;
     }
 }
@@ -1,9 +1,16 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use serenity::all::{AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, ResolvedOption, ResolvedValue};
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+        preclaim_end: i64 = ("preclaim-end", "Time preclaims close, as UNIX timestamp"),
+    }
+}
 
 pub struct ReschedulePreclaimsCommand {}
 
@@ -11,11 +18,7 @@ impl Command for ReschedulePreclaimsCommand {
     const NAME: &'static str = "reschedule-preclaims";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Sets a new preclaim end for a world")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "preclaim-end", "Time preclaims close, as UNIX timestamp").required(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Sets a new preclaim end for a world").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -26,21 +29,9 @@ impl Command for ReschedulePreclaimsCommand {
             return;
         }
 
-        let mut world = "";
-        let mut preclaim_end = 0;
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("preclaim-end", ResolvedValue::Integer(value)) => preclaim_end = value,
-                _ => (),
-            }
-        }
-
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
+        let Some(Options { world, preclaim_end }) = Options::parse(&ctx, &command).await else {
             return;
-        }
+        };
 
         let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
 
@@ -50,6 +41,8 @@ impl Command for ReschedulePreclaimsCommand {
         }
 
         if query!("UPDATE worlds SET preclaim_end = ? WHERE name = ?", preclaim_end, world).execute(&bot.db).await.is_ok() {
+            bot.audit(AuditEvent::new(command.user.id, Self::NAME, format!("Set preclaim end to {preclaim_end}")).world(&world))
+                .await;
             command.simple_reply(&ctx, format!("Successfully set preclaims to end at <t:{preclaim_end}:f>")).await;
         } else {
             command.simple_reply(&ctx, "Failed to set preclaim end").await;
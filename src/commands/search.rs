@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    AutocompleteOption, ButtonStyle, Colour, CommandInteraction, CommandType, ComponentInteraction, Context, CreateActionRow, CreateButton, CreateCommand, CreateEmbed, CreateEmbedFooter,
+    CreateInteractionResponse, EditInteractionResponse, ModalInteraction,
+};
+use sqlx::query;
+
+use crate::{
+    autocomplete::Autocomplete,
+    commands::Command,
+    custom_id::{CustomId, Fields},
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
+    scrape::Status,
+    util::SimpleReply,
+    Bot,
+};
+
+#[derive(Serialize, Deserialize)]
+struct Filter {
+    game: Option<String>,
+    status: Option<i64>,
+    reality: Option<String>,
+    free: Option<bool>,
+    min_points: Option<i64>,
+    max_points: Option<i64>,
+    claimed: Option<bool>,
+}
+
+pub struct Results {
+    filter: String,
+    slots: Vec<Slot>,
+}
+
+#[derive(Clone)]
+pub struct Slot {
+    id: i64,
+    world: String,
+    reality: Option<String>,
+    name: String,
+    games: String,
+    status: i64,
+    free: bool,
+    points: i64,
+    player: Option<i64>,
+}
+
+command_options! {
+    struct Options {
+        game: Option<String> = ("game", "Only show slots containing this game").set_autocomplete(true),
+        status: Option<i64> = ("status", "Only show slots with this status")
+            .add_int_choice("Unstarted", 0)
+            .add_int_choice("In Progress", 1)
+            .add_int_choice("Goal", 2)
+            .add_int_choice("All Checks", 3)
+            .add_int_choice("Done", 4),
+        reality: Option<String> = ("reality", "Only show slots in this reality").set_autocomplete(true),
+        free: Option<bool> = ("free", "Only show free claims, or only slots that are not free claims"),
+        min_points: Option<i64> = ("min-points", "Only show slots worth at least this many points"),
+        max_points: Option<i64> = ("max-points", "Only show slots worth at most this many points"),
+        claimed: Option<bool> = ("claimed", "Only show claimed slots, or only unclaimed slots"),
+    }
+}
+
+pub struct SearchCommand {}
+
+impl Command for SearchCommand {
+    const NAME: &'static str = "search";
+
+    fn register() -> CreateCommand {
+        Options::add_options(CreateCommand::new(Self::NAME).description("Search slots across all worlds").kind(CommandType::ChatInput))
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let Some(Options {
+            game,
+            status,
+            reality,
+            free,
+            min_points,
+            max_points,
+            claimed,
+        }) = Options::parse(&ctx, &command).await
+        else {
+            return;
+        };
+
+        let filter = Filter {
+            game,
+            status,
+            reality,
+            free,
+            min_points,
+            max_points,
+            claimed,
+        };
+        let Some(filter) = bot.save_filter(Self::NAME, &filter).await else {
+            command.simple_reply(&ctx, "Failed to save search filter").await;
+            return;
+        };
+
+        let Some(response) = Self::first_page(bot, filter).await else {
+            command.simple_reply(&ctx, "No slots match this search").await;
+            return;
+        };
+
+        let _ = command.create_response(&ctx.http, CreateInteractionResponse::Message(response.into())).await;
+    }
+
+    async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
+        match interaction.data.autocomplete() {
+            Some(AutocompleteOption { name: "game", value, .. }) => bot.autocomplete_games(ctx, &interaction, value, false).await,
+            Some(AutocompleteOption { name: "reality", value, .. }) => bot.autocomplete_realities(ctx, &interaction, value).await,
+            Some(_) | None => {
+                interaction.no_autocomplete(&ctx).await;
+            }
+        }
+    }
+}
+
+impl Paginate<Results, Slot, Slot, i64> for SearchCommand {
+    const PAGE_SIZE: usize = 5;
+
+    async fn get_containers(bot: &Bot, filter_id: i64, _view: View) -> Vec<Results> {
+        let Some(filter) = bot.saved_filter::<Filter>(Self::NAME, filter_id).await else {
+            return vec![];
+        };
+
+        // Searching for "100%" should match the text, not act as a wildcard
+        let game = filter.game.as_deref().map(|game| game.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+
+        let Ok(response) = query!(
+            "SELECT tracked_slots.id, tracked_worlds.name AS world, realities.name AS \"reality?\", tracked_slots.name, games, status, free, tracked_slots.points, players.snowflake AS \"player?\"
+            FROM tracked_slots INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+            LEFT JOIN realities ON tracked_worlds.reality = realities.id
+            LEFT JOIN claims ON claims.slot = tracked_slots.id
+            LEFT JOIN players ON claims.player = players.id
+            WHERE tracked_worlds.archived = 0
+            AND (?1 IS NULL OR games LIKE '%' || ?1 || '%' ESCAPE '\\')
+            AND (?2 IS NULL OR status = ?2)
+            AND (?3 IS NULL OR realities.name = ?3)
+            AND (?4 IS NULL OR (free > 0) = ?4)
+            AND (?5 IS NULL OR tracked_slots.points >= ?5)
+            AND (?6 IS NULL OR tracked_slots.points <= ?6)
+            AND (?7 IS NULL OR (claims.id IS NOT NULL) = ?7)
+            ORDER BY tracked_worlds.id DESC, tracked_slots.name ASC",
+            game,
+            filter.status,
+            filter.reality,
+            filter.free,
+            filter.min_points,
+            filter.max_points,
+            filter.claimed
+        )
+        .fetch_all(&bot.db)
+        .await
+        else {
+            return vec![];
+        };
+
+        if response.is_empty() {
+            return vec![];
+        }
+
+        let mut description = vec![];
+        if let Some(game) = filter.game {
+            description.push(format!("Game: {game}"));
+        }
+        if let Some(status) = filter.status.and_then(Status::from_i64) {
+            description.push(format!("Status: {}", status.as_str()));
+        }
+        if let Some(reality) = filter.reality {
+            description.push(format!("Reality: {reality}"));
+        }
+        if let Some(free) = filter.free {
+            description.push(String::from(if free { "Free claims only" } else { "No free claims" }));
+        }
+        match (filter.min_points, filter.max_points) {
+            (Some(min), Some(max)) => description.push(format!("Points: {min} to {max}")),
+            (Some(min), None) => description.push(format!("Points: at least {min}")),
+            (None, Some(max)) => description.push(format!("Points: at most {max}")),
+            (None, None) => (),
+        }
+        if let Some(claimed) = filter.claimed {
+            description.push(String::from(if claimed { "Claimed only" } else { "Unclaimed only" }));
+        }
+
+        vec![Results {
+            filter: description.join("\n"),
+            slots: response
+                .into_iter()
+                .map(|record| Slot {
+                    id: record.id,
+                    world: record.world,
+                    reality: record.reality,
+                    name: record.name,
+                    games: record.games,
+                    status: record.status,
+                    free: record.free > 0,
+                    points: record.points,
+                    player: record.player,
+                })
+                .collect(),
+        }]
+    }
+
+    fn id_prefix(filter_id: i64) -> CustomId {
+        CustomId::new(Self::NAME).push(filter_id)
+    }
+
+    fn additional_components(components: &mut Vec<CreateActionRow>, _container: &Results, details: &[Slot], _page: usize, _view: View) {
+        let buttons: Vec<_> = details
+            .iter()
+            .filter(|slot| slot.player.is_none())
+            .map(|slot| {
+                CreateButton::new(CustomId::new(Self::NAME).push("claim").push(slot.id).build())
+                    .style(ButtonStyle::Primary)
+                    .label(format!("Claim {}", slot.name.chars().take(70).collect::<String>()))
+            })
+            .collect();
+
+        if !buttons.is_empty() {
+            components.push(CreateActionRow::Buttons(buttons));
+        }
+    }
+}
+
+impl PageContainer<Slot, Slot, i64> for Results {
+    fn items(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    fn page_setup(&self) -> CreateEmbed {
+        let embed = CreateEmbed::new().title("Search results").colour(Colour::DARK_PURPLE);
+
+        if self.filter.is_empty() { embed } else { embed.description(&self.filter) }.footer(CreateEmbedFooter::new(format!("{} slots", self.slots.len())))
+    }
+}
+
+impl PageItem<Slot, i64> for Slot {
+    async fn details(&self, _bot: &Bot, _extra: i64) -> Option<Slot> {
+        Some(self.clone())
+    }
+}
+
+impl PageDetails for Slot {
+    fn field(&self) -> (String, String, bool) {
+        let mut lines = vec![
+            if let Some(player) = self.player {
+                format!("**Claimed by**: <@{player}>")
+            } else {
+                String::from("*Unclaimed*")
+            },
+            format!("**Games**: {}", self.games),
+            format!("**Status**: {}", Status::from_i64(self.status).map(|status| status.as_str()).unwrap_or("Unknown")),
+            format!("**Points**: {}", self.points),
+        ];
+
+        if self.free {
+            lines.push(String::from("*Free claim*"));
+        }
+
+        (
+            format!(
+                "{} in {}{}",
+                self.name,
+                self.world,
+                if let Some(reality) = &self.reality { format!(" [{reality}]") } else { String::new() }
+            ),
+            lines.join("\n"),
+            false,
+        )
+    }
+}
+
+impl SearchCommand {
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, mut fields: Fields<'_>) {
+        if fields.tag("claim") {
+            let Some(slot) = fields.next() else {
+                interaction.simple_reply(&ctx, "Malformed slot").await;
+                return;
+            };
+
+            let _ = interaction.defer_ephemeral(&ctx.http).await;
+
+            let content = match bot.claim(&interaction.user, slot).await {
+                Ok(content) => {
+                    bot.clear_page_cache(interaction.message.id);
+                    content
+                }
+                Err(content) => content,
+            };
+
+            let _ = interaction.edit_response(&ctx.http, EditInteractionResponse::new().content(content)).await;
+            return;
+        }
+
+        let Some(filter_id) = fields.next() else {
+            interaction.simple_reply(&ctx, "Malformed search filter").await;
+            return;
+        };
+
+        if !Self::try_handle_interaction(bot, &ctx, interaction, fields, filter_id).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, mut fields: Fields<'_>) {
+        let Some(filter_id) = fields.next() else {
+            interaction.simple_reply(&ctx, "Malformed search filter").await;
+            return;
+        };
+
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, filter_id).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+}
@@ -1,11 +1,17 @@
 use std::iter::once;
 
-use serenity::all::{
-    AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, CreateMessage, EditInteractionResponse, ResolvedOption, ResolvedValue,
-};
+use serenity::all::{AutocompleteOption, ChannelId, CommandInteraction, CommandType, Context, CreateCommand, CreateMessage, EditInteractionResponse, ResolvedOption, ResolvedValue};
 use sqlx::query;
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, autocomplete::Autocomplete, channels::STATUS_CHANNEL, commands::Command, discord::Discord, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+        slot: String = ("slot", "Name of the slot").set_autocomplete(true),
+        description: String = ("description", "Status description").set_autocomplete(true),
+    }
+}
 
 pub struct StatusCommand {}
 
@@ -13,66 +19,11 @@ impl Command for StatusCommand {
     const NAME: &'static str = "status";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Report the status of a slot")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot", "Name of the slot").required(true).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "description", "Status description")
-                    .required(true)
-                    .set_autocomplete(true),
-            )
+        Options::add_options(CreateCommand::new(Self::NAME).description("Report the status of a slot").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut world = "";
-        let mut slot = "";
-        let mut description = "";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("slot", ResolvedValue::String(value)) => slot = value,
-                ("description", ResolvedValue::String(value)) => description = value,
-                _ => (),
-            }
-        }
-
-        let slot_id = if let Ok(response) = query!("SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ?)", slot, world)
-            .fetch_one(&bot.db)
-            .await
-        {
-            response.id
-        } else {
-            command.simple_reply(&ctx, "Failed to get slot").await;
-            return;
-        };
-
-        let Some(player) = bot.get_player(i64::from(command.user.id), &command.user.name).await else {
-            command.simple_reply(&ctx, "Failed to get user").await;
-            return;
-        };
-
-        let _ = command.defer_ephemeral(&ctx.http).await;
-
-        if query!("INSERT INTO updates (slot, player, description) VALUES (?, ?, ?)", slot_id, player.id, description)
-            .execute(&bot.db)
-            .await
-            .is_ok()
-        {
-            if let Some(status_channel) = Bot::status_channel(&ctx).await {
-                let _ = status_channel
-                    .send_message(&ctx, CreateMessage::new().content(format!("[{}] [{world}] [{slot}] {description}", command.user.display_name())))
-                    .await;
-            }
-
-            let _ = command
-                .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Successfully updated status of {slot} in {world}")))
-                .await;
-        } else {
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to update status")).await;
-        }
+        Self::run(bot, &ctx, &command).await;
     }
 
     async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
@@ -106,7 +57,7 @@ impl Command for StatusCommand {
                     }
 
                     if let (Some(world), Some(slot), Some(player)) = (world, slot, bot.get_player(i64::from(interaction.user.id), &interaction.user.name).await) {
-                        if let Ok(response) = query!("SELECT description FROM updates WHERE player = ? AND slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ?)) ORDER BY timestamp DESC LIMIT 1", player.id, slot, world)
+                        if let Ok(response) = query!("SELECT description FROM updates WHERE player = ? AND slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world IN (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0)) ORDER BY timestamp DESC LIMIT 1", player.id, slot, world)
                         .fetch_one(&bot.db)
                         .await
                         {
@@ -123,3 +74,54 @@ impl Command for StatusCommand {
         }
     }
 }
+
+impl StatusCommand {
+    pub async fn run(bot: &Bot, ctx: &impl Discord, command: &CommandInteraction) {
+        let Some(Options { world, slot, description }) = Options::parse(ctx, command).await else {
+            return;
+        };
+
+        let slot_id = if let Ok(response) = query!(
+            "SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0)",
+            slot,
+            world
+        )
+        .fetch_one(&bot.db)
+        .await
+        {
+            response.id
+        } else {
+            command.simple_reply(ctx, "Failed to get slot").await;
+            return;
+        };
+
+        let Some(player) = bot.get_player(i64::from(command.user.id), &command.user.name).await else {
+            command.simple_reply(ctx, "Failed to get user").await;
+            return;
+        };
+
+        let _ = ctx.defer_ephemeral(command).await;
+
+        if query!("INSERT INTO updates (slot, player, description) VALUES (?, ?, ?)", slot_id, player.id, description)
+            .execute(&bot.db)
+            .await
+            .is_ok()
+        {
+            bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Posted update").world(&world).slot(&slot)).await;
+
+            let _ = ctx
+                .send_message(
+                    ChannelId::new(STATUS_CHANNEL),
+                    CreateMessage::new().content(format!("[{}] [{world}] [{slot}] {description}", command.user.display_name())),
+                )
+                .await;
+
+            let _ = ctx
+                .edit_response(&command.token, EditInteractionResponse::new().content(format!("Successfully updated status of {slot} in {world}")))
+                .await;
+        } else {
+            bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Failed to update status").world(&world).slot(&slot)).await;
+            let _ = ctx.edit_response(&command.token, EditInteractionResponse::new().content("Failed to update status")).await;
+        }
+    }
+}
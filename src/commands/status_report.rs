@@ -1,15 +1,23 @@
-use std::fmt::Display;
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::DateTime;
 
+use serde_json::to_string;
 use serenity::all::{
-    AutocompleteOption, Colour, CommandInteraction, CommandOptionType, CommandType, ComponentInteraction, ComponentInteractionDataKind, Context, CreateActionRow, CreateCommand, CreateCommandOption,
-    CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse, ResolvedOption, ResolvedValue,
+    AutocompleteOption, Colour, CommandInteraction, CommandType, ComponentInteraction, ComponentInteractionDataKind, Context, CreateActionRow, CreateAttachment, CreateCommand, CreateEmbed,
+    CreateEmbedFooter, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse, ModalInteraction, ResolvedOption, ResolvedValue,
 };
 use sqlx::query;
 
 use crate::{
     autocomplete::Autocomplete,
     commands::Command,
-    paginate::{PageContainer, PageDetails, PageItem, Paginate},
+    custom_id::{CustomId, Fields},
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
     scrape::Status,
     util::SimpleReply,
     Bot,
@@ -22,16 +30,16 @@ enum Sort {
     Alphabetical,
 }
 
-struct World {
+pub struct World {
     name: String,
     checks: i64,
     checks_total: i64,
     slots: Vec<SlotId>,
 }
 
-struct SlotId(i64);
+pub struct SlotId(i64);
 
-struct Slot {
+pub struct Slot {
     id: i64,
     name: String,
     player: String,
@@ -48,10 +56,15 @@ struct Update {
     description: String,
 }
 
-#[derive(Clone, Copy)]
-struct Extra {
-    world_id: i64,
-    sort: Sort,
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+        sort: Option<String> = ("sort", "How to sort the slot")
+            .add_string_choice("Last check sent", "sent")
+            .add_string_choice("Checks done", "checks")
+            .add_string_choice("Alphabetical", "alphabetical"),
+        export: Option<bool> = ("export", "Attach the whole report as a CSV and a Markdown summary"),
+    }
 }
 
 pub struct StatusReportCommand {}
@@ -60,48 +73,26 @@ impl Command for StatusReportCommand {
     const NAME: &'static str = "status-report";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Gets a status report")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "sort", "How to sort the slot")
-                    .required(false)
-                    .add_string_choice("Last check sent", "sent")
-                    .add_string_choice("Checks done", "checks")
-                    .add_string_choice("Alphabetical", "alphabetical"),
-            )
+        Options::add_options(CreateCommand::new(Self::NAME).description("Gets a status report").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut world = "";
-        let mut sort = "sent";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("sort", ResolvedValue::String(value)) => sort = value,
-                _ => (),
-            }
-        }
-
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
+        let Some(Options { world, sort, export }) = Options::parse(&ctx, &command).await else {
             return;
-        }
+        };
 
-        let Some(sort) = Sort::try_from(sort) else {
+        let export = export.unwrap_or_default();
+
+        let Some(sort) = Sort::try_from(sort.as_deref().unwrap_or("sent")) else {
             command.simple_reply(&ctx, "Invalid sort option").await;
             return;
         };
 
-        let world_id = if let Ok(response) = query!("SELECT id FROM tracked_worlds WHERE name = ? LIMIT 1", world).fetch_one(&bot.db).await {
-            if let Some(id) = response.id {
-                id
-            } else {
-                command.simple_reply(&ctx, "Failed to get world").await;
-                return;
-            }
+        let world_id = if let Ok(response) = query!("SELECT id AS \"id!\" FROM tracked_worlds WHERE name = ? AND archived = 0 LIMIT 1", world)
+            .fetch_one(&bot.db)
+            .await
+        {
+            response.id
         } else {
             command.simple_reply(&ctx, "Failed to get world").await;
             return;
@@ -110,11 +101,29 @@ impl Command for StatusReportCommand {
         let _ = command.defer_ephemeral(&ctx).await;
 
         if bot.privileged.contains(&command.user.id) {
-            bot.update_scrape(world).await;
+            bot.update_scrape(&world).await;
             bot.push_needed().await;
         }
 
-        let Some(response) = Self::first_page(bot, Extra { world_id, sort }).await else {
+        if export {
+            let Some((csv, summary)) = Self::export(bot, world_id).await else {
+                let _ = command.edit_response(&ctx, EditInteractionResponse::new().content("Failed to export status report")).await;
+                return;
+            };
+
+            let _ = command
+                .edit_response(
+                    &ctx,
+                    EditInteractionResponse::new()
+                        .content(format!("Status report for {world}"))
+                        .new_attachment(CreateAttachment::bytes(csv, format!("{world}.csv")))
+                        .new_attachment(CreateAttachment::bytes(summary, format!("{world}.md"))),
+                )
+                .await;
+            return;
+        }
+
+        let Some(response) = Self::page(bot, world_id, View { sort: sort.as_i(), filter: 0 }, 0).await else {
             let _ = command.edit_response(&ctx, EditInteractionResponse::new().content("Failed to get status report")).await;
             return;
         };
@@ -170,10 +179,12 @@ impl Sort {
     }
 }
 
-impl Paginate<World, SlotId, Slot, Extra> for StatusReportCommand {
+impl Paginate<World, SlotId, Slot, i64> for StatusReportCommand {
     const PAGE_SIZE: usize = 10;
+    const SORTS: &'static [&'static str] = &["Last check sent", "Checks done", "Alphabetical"];
 
-    async fn get_containers(bot: &Bot, Extra { world_id, sort }: Extra) -> Vec<World> {
+    async fn get_containers(bot: &Bot, world_id: i64, view: View) -> Vec<World> {
+        let sort = Sort::try_from_i(view.sort).unwrap_or(Sort::Sent);
         let Ok(world_name) = query!("SELECT name FROM tracked_worlds WHERE id = ? LIMIT 1", world_id).fetch_one(&bot.db).await else {
             return vec![];
         };
@@ -219,10 +230,10 @@ impl Paginate<World, SlotId, Slot, Extra> for StatusReportCommand {
         }]
     }
 
-    fn additional_components(components: &mut Vec<CreateActionRow>, _container: &World, details: &[Slot]) {
+    fn additional_components(components: &mut Vec<CreateActionRow>, _container: &World, details: &[Slot], _page: usize, _view: View) {
         components.push(CreateActionRow::SelectMenu(
             CreateSelectMenu::new(
-                "status-report-select",
+                CustomId::new(Self::NAME).push("select").build(),
                 CreateSelectMenuKind::String {
                     options: details.iter().map(|slot| CreateSelectMenuOption::new(&slot.name, slot.id.to_string())).collect(),
                 },
@@ -233,12 +244,16 @@ impl Paginate<World, SlotId, Slot, Extra> for StatusReportCommand {
         ));
     }
 
-    fn id_prefix(_container: &World, extra: Extra) -> impl Display {
-        format!("{}-{}-{}", Self::NAME, extra.world_id, extra.sort.as_i())
+    fn id_prefix(world_id: i64) -> CustomId {
+        CustomId::new(Self::NAME).push(world_id)
+    }
+
+    async fn details_for_page(bot: &Bot, items: &[SlotId], _world_id: i64) -> Vec<Slot> {
+        slot_details(bot, &items.iter().map(|slot| slot.0).collect::<Vec<_>>()).await
     }
 }
 
-impl PageContainer<SlotId, Slot, Extra> for World {
+impl PageContainer<SlotId, Slot, i64> for World {
     fn items(&self) -> &[SlotId] {
         &self.slots
     }
@@ -251,44 +266,72 @@ impl PageContainer<SlotId, Slot, Extra> for World {
     }
 }
 
-impl PageItem<Slot, Extra> for SlotId {
-    async fn details(&self, bot: &Bot, _extra: Extra) -> Option<Slot> {
-        let response = query!("SELECT name, status, checks, checks_total, last_activity FROM tracked_slots WHERE id = ? LIMIT 1", self.0)
-            .fetch_one(&bot.db)
-            .await
-            .ok()?;
-        let player = if let Ok(response) = query!("SELECT snowflake FROM claims INNER JOIN players ON claims.player = players.id WHERE slot = ?", self.0)
-            .fetch_one(&bot.db)
-            .await
-        {
-            format!("**Claimed by**: <@{}>", response.snowflake)
-        } else {
-            String::from("*Unclaimed*")
-        };
-        let mut updates = vec![];
-        if let Ok(status_response) = query!("SELECT timestamp, players.snowflake as player_id, description FROM updates INNER JOIN players ON updates.player = players.id WHERE slot = ? GROUP BY updates.player HAVING timestamp = MAX(timestamp) ORDER BY timestamp DESC LIMIT 8", self.0).fetch_all(&bot.db).await {
-            for record in status_response {
-                if let (Some(timestamp), Some(player), Some(mut description)) = (record.timestamp, record.player_id, record.description) {
-                    if description.len() > 120 {
-                        description.truncate(100);
-                        description.push_str("...");
-                    }
-                    updates.push(Update { timestamp, player, description });
+impl PageItem<Slot, i64> for SlotId {
+    async fn details(&self, bot: &Bot, _world_id: i64) -> Option<Slot> {
+        slot_details(bot, &[self.0]).await.pop()
+    }
+}
+
+async fn slot_details(bot: &Bot, slot_ids: &[i64]) -> Vec<Slot> {
+    let Ok(ids) = to_string(slot_ids) else {
+        return vec![];
+    };
+
+    let Ok(response) = query!(
+        "SELECT tracked_slots.id AS \"id!\", tracked_slots.name, status, checks, checks_total, last_activity, players.snowflake AS \"player?\" FROM tracked_slots
+        LEFT JOIN claims ON claims.slot = tracked_slots.id
+        LEFT JOIN players ON claims.player = players.id
+        WHERE tracked_slots.id IN (SELECT value FROM json_each(?))",
+        ids
+    )
+    .fetch_all(&bot.db)
+    .await
+    else {
+        return vec![];
+    };
+
+    let mut updates: HashMap<i64, Vec<Update>> = HashMap::new();
+    if let Ok(status_response) = query!("SELECT slot AS \"slot!\", timestamp, players.snowflake as player_id, description FROM updates INNER JOIN players ON updates.player = players.id WHERE slot IN (SELECT value FROM json_each(?)) GROUP BY slot, updates.player HAVING timestamp = MAX(timestamp) ORDER BY timestamp DESC", ids).fetch_all(&bot.db).await {
+        for record in status_response {
+            if let (Some(timestamp), Some(player), Some(mut description)) = (record.timestamp, record.player_id, record.description) {
+                let slot_updates = updates.entry(record.slot).or_default();
+                if slot_updates.len() >= 8 {
+                    continue;
                 }
+
+                if description.len() > 120 {
+                    description.truncate(100);
+                    description.push_str("...");
+                }
+                slot_updates.push(Update { timestamp, player, description });
             }
         }
+    }
 
-        Some(Slot {
-            id: self.0,
-            name: response.name,
-            player,
-            status: response.status,
-            checks: response.checks,
-            checks_total: response.checks_total,
-            last_activity: response.last_activity,
-            updates,
+    let mut slots: HashMap<i64, Slot> = response
+        .into_iter()
+        .map(|record| {
+            (
+                record.id,
+                Slot {
+                    id: record.id,
+                    name: record.name,
+                    player: if let Some(player) = record.player {
+                        format!("**Claimed by**: <@{player}>")
+                    } else {
+                        String::from("*Unclaimed*")
+                    },
+                    status: record.status,
+                    checks: record.checks,
+                    checks_total: record.checks_total,
+                    last_activity: record.last_activity,
+                    updates: updates.remove(&record.id).unwrap_or_default(),
+                },
+            )
         })
-    }
+        .collect();
+
+    slot_ids.iter().filter_map(|id| slots.remove(id)).collect()
 }
 
 impl PageDetails for Slot {
@@ -316,8 +359,92 @@ impl PageDetails for Slot {
 }
 
 impl StatusReportCommand {
-    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, id: &str) {
-        if id == "select" {
+    async fn export(bot: &Bot, world_id: i64) -> Option<(String, String)> {
+        let world = query!("SELECT name FROM tracked_worlds WHERE id = ? LIMIT 1", world_id).fetch_one(&bot.db).await.ok()?;
+        let response = query!(
+            "SELECT tracked_slots.name, players.name AS \"player?\", status, checks, checks_total, last_activity,
+            (SELECT description FROM updates WHERE updates.slot = tracked_slots.id ORDER BY timestamp DESC LIMIT 1) AS \"latest_update?: String\",
+            (SELECT timestamp FROM updates WHERE updates.slot = tracked_slots.id ORDER BY timestamp DESC LIMIT 1) AS \"latest_update_at?: i64\"
+            FROM tracked_slots LEFT JOIN claims ON claims.slot = tracked_slots.id LEFT JOIN players ON claims.player = players.id
+            WHERE tracked_slots.world = ? ORDER BY tracked_slots.name ASC",
+            world_id
+        )
+        .fetch_all(&bot.db)
+        .await
+        .ok()?;
+
+        let mut csv = vec![String::from("slot,player,status,checks,checks_total,last_activity,latest_update")];
+        let mut rows = vec![];
+        let mut counts = [0; 5];
+        let mut checks = 0;
+        let mut checks_total = 0;
+
+        for record in &response {
+            let status = Status::from_i64(record.status).map(|status| status.as_str()).unwrap_or("Unknown");
+            let player = record.player.as_deref().unwrap_or("");
+            let last_activity = record.last_activity.map(|minutes| minutes.to_string()).unwrap_or_default();
+            let latest_update = match (&record.latest_update, record.latest_update_at) {
+                (Some(update), Some(timestamp)) => format!("[{}] {update}", format_timestamp(timestamp)),
+                (Some(update), None) => update.to_owned(),
+                (None, _) => String::new(),
+            };
+
+            if let Some(count) = usize::try_from(record.status).ok().and_then(|status| counts.get_mut(status)) {
+                *count += 1;
+            }
+            checks += record.checks;
+            checks_total += record.checks_total;
+
+            csv.push(
+                [
+                    &record.name,
+                    player,
+                    status,
+                    &record.checks.to_string(),
+                    &record.checks_total.to_string(),
+                    &last_activity,
+                    &latest_update,
+                ]
+                .map(csv_field)
+                .join(","),
+            );
+            rows.push(format!(
+                "| {} | {} | {status} | {}/{} | {} |",
+                markdown_cell(&record.name),
+                if player.is_empty() { String::from("*Unclaimed*") } else { markdown_cell(player) },
+                record.checks,
+                record.checks_total,
+                markdown_cell(&latest_update)
+            ));
+        }
+
+        let mut summary = vec![
+            format!("# {}", world.name),
+            String::new(),
+            format!("Exported {}", format_timestamp(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64)),
+            String::new(),
+            format!("**Total checks**: {checks}/{checks_total}"),
+            String::new(),
+        ];
+
+        for (status, count) in counts.into_iter().enumerate() {
+            if let Some(status) = Status::from_i64(status as i64).filter(|_| count > 0) {
+                summary.push(format!("- {}: {count}", status.as_str()));
+            }
+        }
+
+        summary.extend([
+            String::new(),
+            String::from("| Slot | Player | Status | Checks | Latest update |"),
+            String::from("| --- | --- | --- | --- | --- |"),
+        ]);
+        summary.extend(rows);
+
+        Some((csv.join("\n") + "\n", summary.join("\n") + "\n"))
+    }
+
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, mut fields: Fields<'_>) {
+        if fields.tag("select") {
             let slot_id = if let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind {
                 if let Some(slot_id) = values.first() {
                     if let Ok(slot_id) = slot_id.parse::<i64>() {
@@ -396,31 +523,39 @@ impl StatusReportCommand {
             } else {
                 let _ = interaction.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to get slot info")).await;
             }
-        } else if let [world_id, sort, "page", page] = id.split('-').collect::<Vec<_>>().as_slice() {
-            if let Ok(new_page) = page.parse::<usize>() {
-                let Ok(world_id) = world_id.parse() else {
-                    interaction.simple_reply(&ctx, "Malformed world id").await;
-                    return;
-                };
-
-                let Some(sort) = sort.parse().ok().and_then(Sort::try_from_i) else {
-                    interaction.simple_reply(&ctx, "Malformed sort type").await;
-                    return;
-                };
-
-                let extra = Extra { world_id, sort };
-
-                let Some(response) = Self::page(bot, extra, new_page).await else {
-                    interaction.simple_reply(&ctx, "There was an error handling your interraction").await;
-                    return;
-                };
-
-                let _ = interaction.create_response(ctx, CreateInteractionResponse::UpdateMessage(response.into())).await;
-            } else {
-                interaction.simple_reply(&ctx, "Malformed page number").await;
+        } else if let Some(world_id) = fields.next() {
+            if !Self::try_handle_interaction(bot, &ctx, interaction, fields, world_id).await {
+                interaction.simple_reply(&ctx, "Unrecognized interraction").await;
             }
         } else {
+            interaction.simple_reply(&ctx, "Malformed world id").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, mut fields: Fields<'_>) {
+        let Some(world_id) = fields.next() else {
+            interaction.simple_reply(&ctx, "Malformed world id").await;
+            return;
+        };
+
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, world_id).await {
             interaction.simple_reply(&ctx, "Unrecognized interraction").await;
         }
     }
 }
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0).map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string()).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
@@ -1,51 +1,44 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use phf::phf_map;
-use serenity::all::{
-    AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, CreateMessage, EditInteractionResponse, ResolvedOption, ResolvedValue,
-};
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, CreateMessage, EditInteractionResponse};
 use sqlx::query;
 
 use crate::{
+    audit::AuditEvent,
     autocomplete::Autocomplete,
     commands::{get_preclaims::resolve_preclaims, Command},
+    options::command_options,
     scrape::{fetch_tracker, scrape},
     util::SimpleReply,
     Bot,
 };
 
+command_options! {
+    struct Options {
+        tracker: String = ("tracker", "Link to or id of the tracker for this world"),
+        name: String = ("name", "Name of the world").set_autocomplete(true),
+        reality: Option<String> = ("reality", "Name of the reality").set_autocomplete(true),
+        import_claims: Option<String> = ("import-claims", "If claims should be imported from a prior world").set_autocomplete(true),
+        awards_points: Option<bool> = ("awards-points", "If this world awards points for multiarchi. Defaults to true"),
+        use_claims: Option<bool> = ("use-claims", "If claiming slots for this world uses the multiarchi claim pool. Defaults to true"),
+        message: Option<String> = ("message", "Additional message to display"),
+        ping: Option<bool> = ("ping", "If @claims should be pinged. Defaults to true"),
+        claim_deadline: Option<i64> = ("claim-deadline", "Unix timestamp after which claims on unstarted slots expire"),
+        claim_idle_days: Option<i64> = ("claim-idle-days", "Days a claim on an unstarted slot can go without updates before it expires"),
+    }
+}
+
 pub struct TrackWorldCommand {}
 
 impl Command for TrackWorldCommand {
     const NAME: &'static str = "track-world";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Tracks a new world")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "tracker", "Link to or id of the tracker for this world").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "name", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "reality", "Name of the reality")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "import-claims", "If claims should be imported from a prior world")
-                    .required(false)
-                    .set_autocomplete(true),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "awards-points", "If this world awards points for multiarchi. Defaults to true").required(false))
-            .add_option(
-                CreateCommandOption::new(
-                    CommandOptionType::Boolean,
-                    "use-claims",
-                    "If claiming slots for this world uses the multiarchi claim pool. Defaults to true",
-                )
-                .required(false),
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "message", "Additional message to display").required(false))
-            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "ping", "If @claims should be pinged. Defaults to true").required(false))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Tracks a new world").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -56,44 +49,42 @@ impl Command for TrackWorldCommand {
             return;
         }
 
-        let mut tracker = "";
-        let mut world_name = "";
-        let mut reality_name = None;
-        let mut import_claims = "";
-        let mut awards_points = true;
-        let mut use_claims = true;
-        let mut message = None;
-        let mut ping = true;
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("tracker", ResolvedValue::String(value)) => tracker = value,
-                ("name", ResolvedValue::String(value)) => world_name = value,
-                ("reality", ResolvedValue::String(value)) => reality_name = Some(value),
-                ("import-claims", ResolvedValue::String(value)) => import_claims = value,
-                ("awards-points", ResolvedValue::Boolean(value)) => awards_points = value,
-                ("use-claims", ResolvedValue::Boolean(value)) => use_claims = value,
-                ("message", ResolvedValue::String(value)) => message = Some(value),
-                ("ping", ResolvedValue::Boolean(value)) => ping = value,
-                _ => (),
-            }
-        }
+        let Some(Options {
+            tracker,
+            name: world_name,
+            reality: reality_name,
+            import_claims,
+            awards_points,
+            use_claims,
+            message,
+            ping,
+            claim_deadline,
+            claim_idle_days,
+        }) = Options::parse(&ctx, &command).await
+        else {
+            return;
+        };
 
-        if tracker.is_empty() {
-            command.simple_reply(&ctx, "A tracker link or id is required").await;
+        let awards_points = awards_points.unwrap_or(true);
+        let use_claims = use_claims.unwrap_or(true);
+        let ping = ping.unwrap_or(true);
+
+        if claim_idle_days.is_some_and(|days| days < 1) {
+            command.simple_reply(&ctx, "Claim idle days must be a positive integer").await;
             return;
         }
 
-        if world_name.is_empty() {
-            command.simple_reply(&ctx, "A world name is required").await;
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        if claim_deadline.is_some_and(|deadline| deadline <= current_time) {
+            command.simple_reply(&ctx, "Claim deadline must be in the future").await;
             return;
         }
 
-        let reality = if let Some(reality_name) = reality_name {
-            if let Ok(response) = query!("SELECT id FROM realities WHERE name = ? LIMIT 1", reality_name).fetch_one(&bot.db).await {
+        let reality = if let Some(reality_name) = &reality_name {
+            if let Ok(response) = query!("SELECT id FROM realities WHERE name = ? AND archived = 0 LIMIT 1", reality_name).fetch_one(&bot.db).await {
                 Some(response.id)
             } else {
-                command.simple_reply(&ctx, "Failed to get reality").await;
+                command.simple_reply(&ctx, "Failed to get reality. Archived realities cannot be used").await;
                 return;
             }
         } else {
@@ -123,7 +114,7 @@ impl Command for TrackWorldCommand {
             return;
         };
 
-        if let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE id IN (SELECT world FROM tracked_slots WHERE status < 2)")
+        if let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE archived = 0 AND id IN (SELECT world FROM tracked_slots WHERE status < 2)")
             .fetch_all(&bot.db)
             .await
         {
@@ -134,9 +125,16 @@ impl Command for TrackWorldCommand {
 
         bot.push_needed().await;
 
-        let world_id = if let Ok(response) = query!("INSERT INTO tracked_worlds (tracker_id, name, reality) VALUES (?, ?, ?) RETURNING id", tracker_id, world_name, reality)
-            .fetch_one(&bot.db)
-            .await
+        let world_id = if let Ok(response) = query!(
+            "INSERT INTO tracked_worlds (tracker_id, name, reality, claim_deadline, claim_idle_days) VALUES (?, ?, ?, ?, ?) RETURNING id",
+            tracker_id,
+            world_name,
+            reality,
+            claim_deadline,
+            claim_idle_days
+        )
+        .fetch_one(&bot.db)
+        .await
         {
             response.id
         } else {
@@ -146,7 +144,7 @@ impl Command for TrackWorldCommand {
 
         let free = if use_claims { 0 } else { 1 };
         let mut unclaimed_slots = 0;
-        resolve_preclaims(bot, world_name).await;
+        resolve_preclaims(bot, &world_name).await;
         for (slot, data) in data {
             let game_str = game_str(&data.games);
             let points = if awards_points { calc_points(&data.games) } else { 0 };
@@ -170,6 +168,7 @@ impl Command for TrackWorldCommand {
                 response.id
             } else {
                 println!("Failed to insert slot {slot} in world {world_id}");
+                bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Failed to insert slot").world(&world_name).slot(&slot)).await;
                 continue;
             };
 
@@ -181,30 +180,49 @@ impl Command for TrackWorldCommand {
             .fetch_one(&bot.db)
             .await
             {
-                if query!("INSERT INTO claims (slot, player) VALUES (?, ?)", slot_id, response.player).execute(&bot.db).await.is_err() {
+                if query!("INSERT INTO claims (slot, player, timestamp) VALUES (?, ?, strftime('%s', 'now'))", slot_id, response.player)
+                    .execute(&bot.db)
+                    .await
+                    .is_err()
+                {
                     println!("Failed to transfer preclaim to claim for slot {slot} in world {world_id}");
+                    bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Failed to transfer preclaim to claim").world(&world_name).slot(&slot))
+                        .await;
                 }
             } else if let Ok(response) = query!(
-                "SELECT player FROM claims WHERE slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ?)) LIMIT 1",
+                "SELECT player FROM claims WHERE slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world = (SELECT id FROM tracked_worlds WHERE name = ? ORDER BY archived ASC, id DESC LIMIT 1)) LIMIT 1",
                 slot,
                 import_claims
             )
             .fetch_one(&bot.db)
             .await
             {
-                if query!("INSERT INTO claims (slot, player) VALUES (?, ?)", slot_id, response.player).execute(&bot.db).await.is_err() {
+                if query!("INSERT INTO claims (slot, player, timestamp) VALUES (?, ?, strftime('%s', 'now'))", slot_id, response.player)
+                    .execute(&bot.db)
+                    .await
+                    .is_err()
+                {
                     println!("Failed to transfer claim for slot {slot} in world {world_id}");
+                    bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Failed to transfer claim").world(&world_name).slot(&slot)).await;
                 }
             } else {
                 unclaimed_slots += 1;
             }
         }
 
-        if let Some(reality_name) = reality_name {
-            bot.log(&format!("Started tracking world {world_name} in {reality_name}"));
-        } else {
-            bot.log(&format!("Started tracking world {world_name}"));
-        }
+        bot.audit(
+            AuditEvent::new(
+                command.user.id,
+                Self::NAME,
+                if let Some(reality_name) = &reality_name {
+                    format!("Started tracking in {reality_name}")
+                } else {
+                    String::from("Started tracking")
+                },
+            )
+            .world(&world_name),
+        )
+        .await;
         bot.push_needed().await;
 
         let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Started tracking world")).await;
@@ -258,3 +276,15 @@ fn game_str(games: &[String]) -> String {
         .collect::<Vec<_>>()
         .join(", ")
 }
+
+pub fn split_game_str(games: &str) -> impl Iterator<Item = (&str, i64)> {
+    games.split(", ").filter(|game| !game.is_empty()).map(|game| {
+        if let Some((name, count)) = game.rsplit_once(" x") {
+            if let Ok(count) = count.parse() {
+                return (name, count);
+            }
+        }
+
+        (game, 1)
+    })
+}
@@ -1,7 +1,13 @@
-use serenity::all::{CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, ResolvedOption, ResolvedValue};
+use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand, User};
 use sqlx::query;
 
-use crate::{commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        player: Option<User> = ("player", "Player to transfer points to"),
+    }
+}
 
 pub struct TransferPointsCommand {}
 
@@ -9,22 +15,15 @@ impl Command for TransferPointsCommand {
     const NAME: &'static str = "transfer-points";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Transfer points to someone else")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::User, "player", "Player to transfer points to").required(false))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Transfer points to someone else").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
         let user = i64::from(command.user.id);
 
-        let mut target = None;
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            if let ("player", ResolvedValue::User(value, _)) = (option_name, value) {
-                target = Some(value)
-            }
-        }
+        let Some(Options { player: target }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
 
         if let Some(target) = target {
             let target_snowflake = i64::from(target.id);
@@ -38,11 +37,13 @@ impl Command for TransferPointsCommand {
                 return;
             };
             if query!("UPDATE players SET transfer_to = ? WHERE snowflake = ?", target_id, user).execute(&bot.db).await.is_ok() {
+                bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Began transferring points").target(target.id)).await;
                 command.simple_reply(&ctx, format!("Began transferring points to <@{}>", target_snowflake)).await;
             } else {
                 command.simple_reply(&ctx, "Failed to transfer points").await;
             }
         } else if query!("UPDATE players SET transfer_to = NULL WHERE snowflake = ?", user).execute(&bot.db).await.is_ok() {
+            bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Stopped transferring points")).await;
             command.simple_reply(&ctx, "You are no longer transferring your earned points").await;
         } else {
             command.simple_reply(&ctx, "Failed to cancel transferring of points").await;
@@ -1,7 +1,22 @@
-use serenity::all::{AutocompleteOption, CommandInteraction, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption, EditInteractionResponse, ResolvedOption, ResolvedValue};
-use sqlx::query;
+use serenity::all::{AutocompleteOption, CommandInteraction, CommandType, Context, CreateCommand, EditInteractionResponse, ResolvedOption, ResolvedValue};
+use sqlx::{query, query_as};
 
-use crate::{autocomplete::Autocomplete, commands::Command, util::SimpleReply, Bot};
+use crate::{
+    audit::AuditEvent,
+    autocomplete::Autocomplete,
+    commands::Command,
+    options::command_options,
+    undo::{ClaimRow, UndoAction},
+    util::SimpleReply,
+    Bot,
+};
+
+command_options! {
+    struct Options {
+        world: String = ("world", "Name of the world").set_autocomplete(true),
+        slot: String = ("slot", "Name of the slot").set_autocomplete(true),
+    }
+}
 
 pub struct UnclaimCommand {}
 
@@ -9,11 +24,7 @@ impl Command for UnclaimCommand {
     const NAME: &'static str = "unclaim";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("Forcibly unclaims a slot")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(true).set_autocomplete(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "slot", "Name of the slot").required(true).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("Forcibly unclaims a slot").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
@@ -24,44 +35,106 @@ impl Command for UnclaimCommand {
             return;
         }
 
-        let mut world = "";
-        let mut slot = "";
+        let Some(Options { world, slot }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
 
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            match (option_name, value) {
-                ("world", ResolvedValue::String(value)) => world = value,
-                ("slot", ResolvedValue::String(value)) => slot = value,
-                _ => (),
-            }
-        }
+        let _ = command.defer_ephemeral(&ctx.http).await;
 
-        if world.is_empty() {
-            command.simple_reply(&ctx, "A world is required").await;
+        let Ok(mut transaction) = bot.db.begin().await else {
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to create transaction")).await;
             return;
-        }
+        };
 
-        if slot.is_empty() {
-            command.simple_reply(&ctx, "A slot is required").await;
-            return;
-        }
+        let claims = match query_as!(
+            ClaimRow,
+            "SELECT slot, player, public, public_kind, public_expires, timestamp FROM claims WHERE slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0))",
+            slot,
+            world
+        )
+        .fetch_all(&mut *transaction)
+        .await
+        {
+            Ok(claims) => claims,
+            Err(err) => {
+                println!("Failed to get claims: {err}");
+                let _ = transaction.rollback().await;
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to unclaim slot")).await;
+                return;
+            }
+        };
 
-        let _ = command.defer_ephemeral(&ctx.http).await;
+        let released = match query!(
+            "INSERT INTO released_claims (player, reality) SELECT claims.player, tracked_worlds.reality FROM claims
+            INNER JOIN tracked_slots ON claims.slot = tracked_slots.id INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+            WHERE tracked_slots.name = ? AND tracked_worlds.name = ? AND tracked_worlds.archived = 0 RETURNING id AS \"id!\"",
+            slot,
+            world
+        )
+        .fetch_all(&mut *transaction)
+        .await
+        {
+            Ok(released) => released.into_iter().map(|record| record.id).collect(),
+            Err(err) => {
+                println!("Failed to record released claims: {err}");
+                let _ = transaction.rollback().await;
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to unclaim slot")).await;
+                return;
+            }
+        };
 
-        if query!(
-            "DELETE FROM claims WHERE slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ?))",
+        if let Err(err) = query!(
+            "DELETE FROM claims WHERE slot IN (SELECT id FROM tracked_slots WHERE name = ? AND world in (SELECT id FROM tracked_worlds WHERE name = ? AND archived = 0))",
             slot,
             world
         )
-        .execute(&bot.db)
+        .execute(&mut *transaction)
         .await
-        .is_ok()
         {
-            bot.log(&format!("Slot {slot} in {world} was unclaimed"));
-            bot.push_needed().await;
-            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Successfully unclaimed slot")).await;
-        } else {
+            println!("Failed to delete claims: {err}");
+            let _ = transaction.rollback().await;
             let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to unclaim slot")).await;
+            return;
+        }
+
+        let previous_claimant = match claims.first() {
+            Some(claim) => query!("SELECT snowflake FROM players WHERE id = ? LIMIT 1", claim.player)
+                .fetch_optional(&mut *transaction)
+                .await
+                .ok()
+                .flatten()
+                .map(|record| record.snowflake),
+            None => None,
+        };
+        let claims_removed = !claims.is_empty();
+
+        if claims_removed {
+            if let Err(err) = (UndoAction::Unclaim { claims, released })
+                .record(&mut transaction, i64::from(user), Self::NAME, &format!("Unclaimed {slot} in {world}"))
+                .await
+            {
+                println!("Failed to record undo entry: {err}");
+                let _ = transaction.rollback().await;
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to record undo entry. Aborting")).await;
+                return;
+            }
+        }
+
+        if let Err(err) = transaction.commit().await {
+            println!("Failed to commit transaction: {err}");
+            let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Failed to commit transaction. Aborting")).await;
+            return;
+        }
+
+        let mut event = AuditEvent::new(command.user.id, Self::NAME, if claims_removed { "Unclaimed" } else { "Slot was not claimed" })
+            .world(&world)
+            .slot(&slot);
+        if let Some(previous) = previous_claimant {
+            event = event.target(previous);
         }
+        bot.audit(event).await;
+        bot.push_needed().await;
+        let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content("Successfully unclaimed slot")).await;
     }
 
     async fn autocomplete(bot: &Bot, ctx: Context, interaction: CommandInteraction) {
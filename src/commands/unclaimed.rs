@@ -1,20 +1,23 @@
 use std::collections::HashMap;
 
+use serde_json::to_string;
 use serenity::all::{
-    AutocompleteOption, Colour, CommandInteraction, CommandOptionType, CommandType, ComponentInteraction, Context, CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
-    ResolvedOption, ResolvedValue,
+    AutocompleteOption, Colour, CommandInteraction, CommandType, ComponentInteraction, ComponentInteractionDataKind, Context, CreateActionRow, CreateCommand, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, ModalInteraction,
 };
 use sqlx::query;
 
 use crate::{
     autocomplete::Autocomplete,
     commands::Command,
-    paginate::{PageContainer, PageDetails, PageItem, Paginate},
+    custom_id::{CustomId, Fields},
+    options::command_options,
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
     util::SimpleReply,
     Bot,
 };
 
-struct World {
+pub struct World {
     id: i64,
     name: String,
     reality: Option<String>,
@@ -23,43 +26,41 @@ struct World {
 
 pub struct SlotId(i64);
 
-struct Slot {
+pub struct Slot {
+    id: i64,
     name: String,
     games: String,
     free: bool,
 }
 
+command_options! {
+    struct Options {
+        world: Option<String> = ("world", "Name of the world").set_autocomplete(true),
+    }
+}
+
 pub struct UnclaimedCommand {}
 
 impl Command for UnclaimedCommand {
     const NAME: &'static str = "unclaimed";
 
     fn register() -> CreateCommand {
-        CreateCommand::new(Self::NAME)
-            .description("View unclaimed slots")
-            .kind(CommandType::ChatInput)
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "world", "Name of the world").required(false).set_autocomplete(true))
+        Options::add_options(CreateCommand::new(Self::NAME).description("View unclaimed slots").kind(CommandType::ChatInput))
     }
 
     async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
-        let mut world_name = "";
-
-        for ResolvedOption { name: option_name, value, .. } in command.data.options() {
-            if let ("world", ResolvedValue::String(value)) = (option_name, value) {
-                world_name = value
-            }
-        }
+        let Some(Options { world: world_name }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
 
-        let worlds = Self::get_containers(bot, ()).await;
+        let worlds = Self::get_containers(bot, (), View::default()).await;
 
         if worlds.is_empty() {
             command.simple_reply(&ctx, "There are no worlds with unclaimed slots").await;
             return;
         }
 
-        let start_page = if world_name.is_empty() {
-            0
-        } else {
+        let start_page = if let Some(world_name) = world_name {
             let mut start_page = 0;
 
             for world in &worlds {
@@ -75,9 +76,11 @@ impl Command for UnclaimedCommand {
             }
 
             start_page
+        } else {
+            0
         };
 
-        let Some(response) = Self::page(bot, (), start_page).await else {
+        let Some(response) = Self::page(bot, (), View::default(), start_page).await else {
             command.simple_reply(&ctx, "There are no worlds with unclaimed slots").await;
             return;
         };
@@ -96,8 +99,14 @@ impl Command for UnclaimedCommand {
 }
 
 impl Paginate<World, SlotId, Slot> for UnclaimedCommand {
-    async fn get_containers(bot: &Bot, _: ()) -> Vec<World> {
-        if let Ok(response) = query!("SELECT tracked_worlds.id as world_id, tracked_worlds.name AS world_name, realities.name AS reality, tracked_slots.id AS slot_id FROM tracked_worlds INNER JOIN tracked_slots ON tracked_worlds.id = tracked_slots.world LEFT JOIN realities ON tracked_worlds.reality = realities.id WHERE tracked_slots.id NOT IN (SELECT slot FROM claims) ORDER BY tracked_slots.name").fetch_all(&bot.db).await {
+    const SORTS: &'static [&'static str] = &["Name", "Games"];
+    const FILTERS: &'static [&'static str] = &["All slots", "Free claims only", "No free claims"];
+
+    async fn get_containers(bot: &Bot, _: (), view: View) -> Vec<World> {
+        let sort = view.sort as i64;
+        let filter = view.filter as i64;
+
+        if let Ok(response) = query!("SELECT tracked_worlds.id as world_id, tracked_worlds.name AS world_name, realities.name AS reality, tracked_slots.id AS slot_id FROM tracked_worlds INNER JOIN tracked_slots ON tracked_worlds.id = tracked_slots.world LEFT JOIN realities ON tracked_worlds.reality = realities.id WHERE tracked_slots.id NOT IN (SELECT slot FROM claims) AND tracked_worlds.archived = 0 AND (?2 = 0 OR (?2 = 1 AND tracked_slots.free > 0) OR (?2 = 2 AND tracked_slots.free = 0)) ORDER BY CASE WHEN ?1 = 1 THEN tracked_slots.games ELSE tracked_slots.name END, tracked_slots.name", sort, filter).fetch_all(&bot.db).await {
             let mut worlds: HashMap<i64, (String, Option<String>, Vec<SlotId>)> = HashMap::new();
 
             for record in response {
@@ -114,6 +123,28 @@ impl Paginate<World, SlotId, Slot> for UnclaimedCommand {
             vec![]
         }
     }
+
+    async fn details_for_page(bot: &Bot, items: &[SlotId], _extra: ()) -> Vec<Slot> {
+        slot_details(bot, &items.iter().map(|slot| slot.0).collect::<Vec<_>>()).await
+    }
+
+    fn additional_components(components: &mut Vec<CreateActionRow>, _container: &World, details: &[Slot], page: usize, view: View) {
+        if details.is_empty() {
+            return;
+        }
+
+        components.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                CustomId::new(Self::NAME).push("select").push(page).push(view.sort).push(view.filter).build(),
+                CreateSelectMenuKind::String {
+                    options: details.iter().map(|slot| CreateSelectMenuOption::new(&slot.name, slot.id.to_string())).collect(),
+                },
+            )
+            .placeholder("Claim slot")
+            .min_values(1)
+            .max_values(1),
+        ));
+    }
 }
 
 impl PageContainer<SlotId, Slot> for World {
@@ -130,14 +161,38 @@ impl PageContainer<SlotId, Slot> for World {
 
 impl PageItem<Slot> for SlotId {
     async fn details(&self, bot: &Bot, _extra: ()) -> Option<Slot> {
-        let response = query!("SELECT name, games, free FROM tracked_slots WHERE id = ? LIMIT 1", self.0).fetch_one(&bot.db).await.ok()?;
+        slot_details(bot, &[self.0]).await.pop()
+    }
+}
+
+async fn slot_details(bot: &Bot, slot_ids: &[i64]) -> Vec<Slot> {
+    let Ok(ids) = to_string(slot_ids) else {
+        return vec![];
+    };
+
+    let Ok(response) = query!("SELECT id AS \"id!\", name, games, free FROM tracked_slots WHERE id IN (SELECT value FROM json_each(?))", ids)
+        .fetch_all(&bot.db)
+        .await
+    else {
+        return vec![];
+    };
 
-        Some(Slot {
-            name: response.name,
-            games: response.games,
-            free: response.free > 0,
+    let mut slots: HashMap<i64, Slot> = response
+        .into_iter()
+        .map(|record| {
+            (
+                record.id,
+                Slot {
+                    id: record.id,
+                    name: record.name,
+                    games: record.games,
+                    free: record.free > 0,
+                },
+            )
         })
-    }
+        .collect();
+
+    slot_ids.iter().filter_map(|id| slots.remove(id)).collect()
 }
 
 impl PageDetails for Slot {
@@ -147,9 +202,61 @@ impl PageDetails for Slot {
 }
 
 impl UnclaimedCommand {
-    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, id: &str) {
-        if Self::try_handle_interaction(bot, &ctx, interaction, id, ()).await {
-        } else {
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, mut fields: Fields<'_>) {
+        if fields.tag("select") {
+            let (Some(page), Some(view)) = (fields.next::<usize>(), View::decode(&mut fields)) else {
+                interaction.simple_reply(&ctx, "Malformed page number").await;
+                return;
+            };
+
+            let slot_id = if let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind {
+                if let Some(slot_id) = values.first() {
+                    if let Ok(slot_id) = slot_id.parse::<i64>() {
+                        slot_id
+                    } else {
+                        interaction.simple_reply(&ctx, "Malformed slot id").await;
+                        return;
+                    }
+                } else {
+                    interaction.simple_reply(&ctx, "Claims must be of exactly one slot").await;
+                    return;
+                }
+            } else {
+                interaction.simple_reply(&ctx, "Malformed selection data").await;
+                return;
+            };
+
+            let content = match bot.claim(&interaction.user, slot_id).await {
+                Ok(content) => content,
+                Err(reason) => {
+                    interaction.simple_reply(&ctx, reason).await;
+                    return;
+                }
+            };
+
+            bot.clear_page_cache(interaction.message.id);
+
+            let page_count = Self::page_count(&Self::get_containers(bot, (), view).await);
+            let response = if page_count == 0 {
+                CreateInteractionResponseMessage::new()
+                    .content("There are no worlds with unclaimed slots")
+                    .embeds(vec![])
+                    .components(vec![])
+            } else if let Some(response) = Self::page(bot, (), view, page.min(page_count - 1)).await {
+                response.into()
+            } else {
+                CreateInteractionResponseMessage::new().content("There was an error refreshing unclaimed slots")
+            };
+
+            let _ = interaction.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response)).await;
+            let _ = interaction.create_followup(&ctx.http, CreateInteractionResponseFollowup::new().ephemeral(true).content(content)).await;
+        } else if !Self::try_handle_interaction(bot, &ctx, interaction, fields, ()).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, fields: Fields<'_>) {
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, ()).await {
             interaction.simple_reply(&ctx, "Unrecognized interraction").await;
         }
     }
@@ -0,0 +1,50 @@
+use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand, EditInteractionResponse};
+
+use crate::{audit::AuditEvent, commands::Command, options::command_options, util::SimpleReply, Bot};
+
+command_options! {
+    struct Options {
+        entry: Option<i64> = ("entry", "Undo log entry to revert, defaults to your most recent action"),
+    }
+}
+
+pub struct UndoCommand {}
+
+impl Command for UndoCommand {
+    const NAME: &'static str = "undo";
+
+    fn register() -> CreateCommand {
+        Options::add_options(
+            CreateCommand::new(Self::NAME)
+                .description("Reverts a recent unclaim, mark-free, cancel-preclaims or finish-world")
+                .kind(CommandType::ChatInput),
+        )
+    }
+
+    async fn execute(bot: &Bot, ctx: Context, command: CommandInteraction) {
+        let user = command.user.id;
+
+        if !bot.privileged.contains(&user) {
+            command.simple_reply(&ctx, "You do not have permission to use this command").await;
+            return;
+        }
+
+        let Some(Options { entry }) = Options::parse(&ctx, &command).await else {
+            return;
+        };
+
+        let _ = command.defer_ephemeral(&ctx.http).await;
+
+        match bot.undo(i64::from(user), bot.admins.contains(&user), entry).await {
+            Ok(description) => {
+                bot.audit(AuditEvent::new(user, Self::NAME, format!("Undid: {description}"))).await;
+                bot.push_needed().await;
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Restored: {description}"))).await;
+            }
+            Err(reason) => {
+                bot.audit(AuditEvent::new(user, Self::NAME, format!("Failed: {reason}"))).await;
+                let _ = command.edit_response(&ctx.http, EditInteractionResponse::new().content(reason)).await;
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use serenity::all::{CommandInteraction, CommandType, Context, CreateCommand};
 use sqlx::query;
 
-use crate::{commands::Command, util::SimpleReply, Bot};
+use crate::{audit::AuditEvent, commands::Command, util::SimpleReply, Bot};
 
 pub struct UnpreclaimCommand {}
 
@@ -17,6 +17,7 @@ impl Command for UnpreclaimCommand {
 
         if let Ok(response) = query!("DELETE FROM preclaims WHERE player IN (SELECT id FROM players WHERE snowflake = ?) AND slot IN (SELECT id FROM slots WHERE world IN (SELECT id FROM worlds WHERE preclaim_end > strftime('%s', 'now')))", user).execute(&bot.db).await {
             if response.rows_affected() > 0 {
+                bot.audit(AuditEvent::new(command.user.id, Self::NAME, "Removed preclaim")).await;
                 command.simple_reply(&ctx, "Successfully removed preclaim").await;
             } else {
                 command.simple_reply(&ctx, "Failed to remove preclaim").await;
@@ -1,14 +1,17 @@
 use std::collections::HashMap;
 
+use serde_json::to_string;
 use serenity::all::{
     Colour, CommandInteraction, CommandType, ComponentInteraction, ComponentInteractionDataKind, Context, CreateActionRow, CreateCommand, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
-    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, Timestamp,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, ModalInteraction, Timestamp,
 };
 use sqlx::query;
 
 use crate::{
+    audit::AuditEvent,
     commands::Command,
-    paginate::{PageContainer, PageDetails, PageItem, Paginate},
+    custom_id::{CustomId, Fields},
+    paginate::{PageContainer, PageDetails, PageItem, Paginate, View},
     util::SimpleReply,
     Bot, Player, UNSPENT_POINTS_LIMIT,
 };
@@ -59,7 +62,7 @@ impl Command for ViewPreclaimsCommand {
 impl Paginate<World, SlotId, Slot, &Player> for ViewPreclaimsCommand {
     const PAGE_SIZE: usize = 24;
 
-    async fn get_containers(bot: &Bot, _: &Player) -> Vec<World> {
+    async fn get_containers(bot: &Bot, _: &Player, _view: View) -> Vec<World> {
         if let Ok(response) = query!("SELECT worlds.name AS world_name, realities.name AS reality, preclaim_end, slots.id AS slot_id FROM worlds INNER JOIN slots ON worlds.id = slots.world LEFT JOIN realities ON worlds.reality = realities.id WHERE preclaim_end > strftime('%s', 'now') ORDER BY slots.name").fetch_all(&bot.db).await {
             let mut worlds: HashMap<String, (Option<String>, i64, Vec<SlotId>)> = HashMap::new();
 
@@ -86,10 +89,10 @@ impl Paginate<World, SlotId, Slot, &Player> for ViewPreclaimsCommand {
         }
     }
 
-    fn additional_components(components: &mut Vec<CreateActionRow>, _container: &World, details: &[Slot]) {
+    fn additional_components(components: &mut Vec<CreateActionRow>, _container: &World, details: &[Slot], _page: usize, _view: View) {
         components.push(CreateActionRow::SelectMenu(
             CreateSelectMenu::new(
-                "view-preclaims-select",
+                CustomId::new(Self::NAME).push("select").build(),
                 CreateSelectMenuKind::String {
                     options: details.iter().map(|slot| CreateSelectMenuOption::new(&slot.name, slot.id.to_string())).collect(),
                 },
@@ -100,6 +103,10 @@ impl Paginate<World, SlotId, Slot, &Player> for ViewPreclaimsCommand {
         ));
     }
 
+    async fn details_for_page(bot: &Bot, items: &[SlotId], player: &Player) -> Vec<Slot> {
+        slot_details(bot, &items.iter().map(|slot| slot.0).collect::<Vec<_>>(), player).await
+    }
+
     async fn additional_fields(bot: &Bot, fields: &mut Vec<(String, String, bool)>, player: &Player) {
         let current_preclaim = query!("SELECT name FROM slots INNER JOIN preclaims ON preclaims.slot = slots.id WHERE status = 0 AND player = ?", player.id)
             .fetch_one(&bot.db)
@@ -127,23 +134,47 @@ impl PageContainer<SlotId, Slot, &Player> for World {
 
 impl PageItem<Slot, &Player> for SlotId {
     async fn details(&self, bot: &Bot, player: &Player) -> Option<Slot> {
-        let response = query!("SELECT name, games, notes, points FROM slots WHERE id = ? LIMIT 1", self.0).fetch_one(&bot.db).await.ok()?;
-        let current_preclaim = query!("SELECT status FROM preclaims WHERE slot = ? AND player = ? LIMIT 1", self.0, player.id)
-            .fetch_one(&bot.db)
-            .await
-            .is_ok();
-
-        Some(Slot {
-            id: self.0,
-            name: response.name,
-            games: response.games,
-            notes: response.notes,
-            points: response.points,
-            current_preclaim,
-        })
+        slot_details(bot, &[self.0], player).await.pop()
     }
 }
 
+async fn slot_details(bot: &Bot, slot_ids: &[i64], player: &Player) -> Vec<Slot> {
+    let Ok(ids) = to_string(slot_ids) else {
+        return vec![];
+    };
+
+    let Ok(response) = query!(
+        "SELECT id AS \"id!\", name, games, notes, points, EXISTS(SELECT 1 FROM preclaims WHERE preclaims.slot = slots.id AND preclaims.player = ?) AS \"current_preclaim!: bool\"
+        FROM slots WHERE id IN (SELECT value FROM json_each(?))",
+        player.id,
+        ids
+    )
+    .fetch_all(&bot.db)
+    .await
+    else {
+        return vec![];
+    };
+
+    let mut slots: HashMap<i64, Slot> = response
+        .into_iter()
+        .map(|record| {
+            (
+                record.id,
+                Slot {
+                    id: record.id,
+                    name: record.name,
+                    games: record.games,
+                    notes: record.notes,
+                    points: record.points,
+                    current_preclaim: record.current_preclaim,
+                },
+            )
+        })
+        .collect();
+
+    slot_ids.iter().filter_map(|id| slots.remove(id)).collect()
+}
+
 impl PageDetails for Slot {
     fn field(&self) -> (String, String, bool) {
         (
@@ -160,14 +191,14 @@ impl PageDetails for Slot {
 }
 
 impl ViewPreclaimsCommand {
-    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, id: &str) {
+    pub async fn handle_interraction(bot: &Bot, ctx: Context, interaction: &ComponentInteraction, mut fields: Fields<'_>) {
         let user_id = i64::from(interaction.user.id);
         let Some(player) = bot.get_player(user_id, &interaction.user.name).await else {
             interaction.simple_reply(&ctx, "Failed to get player").await;
             return;
         };
 
-        if id == "select" {
+        if fields.tag("select") {
             let slot_id = if let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind {
                 if let Some(slot_id) = values.first() {
                     if let Ok(slot_id) = slot_id.parse::<i64>() {
@@ -185,7 +216,20 @@ impl ViewPreclaimsCommand {
                 return;
             };
 
+            let Ok(slot) = query!(
+                "SELECT slots.name, worlds.name AS world FROM slots INNER JOIN worlds ON worlds.id = slots.world WHERE slots.id = ? LIMIT 1",
+                slot_id
+            )
+            .fetch_one(&bot.db)
+            .await
+            else {
+                interaction.simple_reply(&ctx, "Failed to get slot").await;
+                return;
+            };
+
             if let Err(reason) = bot.can_preclaim_slot(player.id, slot_id).await {
+                bot.audit(AuditEvent::new(interaction.user.id, Self::NAME, format!("Rejected: {reason}")).world(&slot.world).slot(&slot.name))
+                    .await;
                 interaction.simple_reply(&ctx, reason).await;
                 return;
             }
@@ -205,6 +249,8 @@ impl ViewPreclaimsCommand {
                 return;
             }
 
+            bot.audit(AuditEvent::new(interaction.user.id, Self::NAME, "Preclaimed").world(&slot.world).slot(&slot.name)).await;
+
             if blocked_by_unspent {
                 interaction
                     .simple_reply(
@@ -215,8 +261,19 @@ impl ViewPreclaimsCommand {
             } else {
                 interaction.simple_reply(&ctx, "Successfully preclaimed slot").await;
             }
-        } else if Self::try_handle_interaction(bot, &ctx, interaction, id, &player).await {
-        } else {
+        } else if !Self::try_handle_interaction(bot, &ctx, interaction, fields, &player).await {
+            interaction.simple_reply(&ctx, "Unrecognized interraction").await;
+        }
+    }
+
+    pub async fn handle_modal(bot: &Bot, ctx: Context, interaction: &ModalInteraction, fields: Fields<'_>) {
+        let user_id = i64::from(interaction.user.id);
+        let Some(player) = bot.get_player(user_id, &interaction.user.name).await else {
+            interaction.simple_reply(&ctx, "Failed to get player").await;
+            return;
+        };
+
+        if !Self::try_handle_modal(bot, &ctx, interaction, fields, &player).await {
             interaction.simple_reply(&ctx, "Unrecognized interraction").await;
         }
     }
@@ -39,7 +39,7 @@ impl Command for WorldsCommand {
         let _ = command.defer_ephemeral(&ctx.http).await;
 
         if bot.privileged.contains(&command.user.id) {
-            if let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE id IN (SELECT world FROM tracked_slots WHERE status < 2)")
+            if let Ok(response) = query!("SELECT name FROM tracked_worlds WHERE archived = 0 AND id IN (SELECT world FROM tracked_slots WHERE status < 2)")
                 .fetch_all(&bot.db)
                 .await
             {
@@ -0,0 +1,88 @@
+use std::{
+    fmt::{Display, Write},
+    str::FromStr,
+};
+
+// Bump whenever the layout of existing ids changes so buttons on old messages are rejected instead of misread
+const VERSION: &str = "1";
+const SEPARATOR: char = ':';
+pub const MAX_LENGTH: usize = 100;
+
+#[derive(Clone)]
+pub struct CustomId(String);
+
+impl CustomId {
+    pub fn new(command: &str) -> Self {
+        Self(format!("{command}{SEPARATOR}{VERSION}"))
+    }
+
+    pub fn push(mut self, field: impl Display) -> Self {
+        let _ = write!(self.0, "{SEPARATOR}{field}");
+        self
+    }
+
+    pub fn build(self) -> String {
+        if self.0.len() > MAX_LENGTH {
+            println!("Custom id {} is longer than {MAX_LENGTH} characters", self.0);
+        }
+
+        self.0
+    }
+}
+
+impl Display for CustomId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub struct StaleId;
+
+pub fn decode(id: &str) -> Result<(&str, Fields<'_>), StaleId> {
+    let mut fields = Fields { id, position: 0 };
+    let command = fields.next_str().ok_or(StaleId)?;
+
+    if fields.next_str() != Some(VERSION) {
+        return Err(StaleId);
+    }
+
+    Ok((command, fields))
+}
+
+pub struct Fields<'a> {
+    id: &'a str,
+    position: usize,
+}
+
+impl<'a> Fields<'a> {
+    pub fn next_str(&mut self) -> Option<&'a str> {
+        let rest = self.id.get(self.position..)?;
+        let (field, consumed) = match rest.split_once(SEPARATOR) {
+            Some((field, _)) => (field, field.len() + 1),
+            None if rest.is_empty() => return None,
+            None => (rest, rest.len()),
+        };
+
+        self.position += consumed;
+        Some(field)
+    }
+
+    pub fn next<T: FromStr>(&mut self) -> Option<T> {
+        self.next_str()?.parse().ok()
+    }
+
+    pub fn tag(&mut self, tag: &str) -> bool {
+        let position = self.position;
+
+        if self.next_str() == Some(tag) {
+            true
+        } else {
+            self.position = position;
+            false
+        }
+    }
+
+    pub fn prefix(&self) -> CustomId {
+        CustomId(self.id[..self.position].trim_end_matches(SEPARATOR).to_owned())
+    }
+}
@@ -0,0 +1,40 @@
+use serenity::{
+    all::{ChannelId, CommandInteraction, Context, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse, InteractionId, UserId},
+    builder::Builder,
+    Result,
+};
+
+// Everything a command sends back to Discord goes through here so commands can be run against a fake in tests
+// There is no user lookup: user options arrive resolved inside the interaction, so no command fetches users from Discord
+pub trait Discord {
+    async fn create_response(&self, id: InteractionId, token: &str, response: CreateInteractionResponse) -> Result<()>;
+
+    async fn edit_response(&self, token: &str, response: EditInteractionResponse) -> Result<()>;
+
+    async fn send_message(&self, channel: ChannelId, message: CreateMessage) -> Result<()>;
+
+    async fn direct_message(&self, user: UserId, message: CreateMessage) -> Result<()>;
+
+    async fn defer_ephemeral(&self, command: &CommandInteraction) -> Result<()> {
+        self.create_response(command.id, &command.token, CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new().ephemeral(true)))
+            .await
+    }
+}
+
+impl Discord for Context {
+    async fn create_response(&self, id: InteractionId, token: &str, response: CreateInteractionResponse) -> Result<()> {
+        response.execute(self, (id, token)).await
+    }
+
+    async fn edit_response(&self, token: &str, response: EditInteractionResponse) -> Result<()> {
+        response.execute(self, token).await.map(|_| ())
+    }
+
+    async fn send_message(&self, channel: ChannelId, message: CreateMessage) -> Result<()> {
+        channel.send_message(self, message).await.map(|_| ())
+    }
+
+    async fn direct_message(&self, user: UserId, message: CreateMessage) -> Result<()> {
+        user.direct_message(self, message).await.map(|_| ())
+    }
+}
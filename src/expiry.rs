@@ -0,0 +1,108 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serenity::all::{CacheHttp, CreateMessage, UserId};
+use sqlx::query;
+
+use crate::{
+    audit::AuditEvent,
+    commands::{claim::ClaimCommand, Command},
+    Bot,
+};
+
+const EXPIRY_WARNING: i64 = 24 * 60 * 60;
+
+impl Bot {
+    pub async fn expire_claims(&self, cache_http: impl CacheHttp) {
+        let Ok(response) = query!(
+            "SELECT claims.id, claims.timestamp, expiry_warned_at, snowflake, tracked_slots.name AS slot, tracked_worlds.name AS world, claim_deadline, claim_idle_days,
+            (SELECT MAX(timestamp) FROM updates WHERE updates.slot = claims.slot AND updates.player = claims.player) AS \"last_update?: i64\"
+            FROM claims INNER JOIN tracked_slots ON claims.slot = tracked_slots.id INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id INNER JOIN players ON claims.player = players.id
+            WHERE tracked_worlds.archived = 0 AND status = 0 AND (claim_deadline IS NOT NULL OR claim_idle_days IS NOT NULL)"
+        )
+        .fetch_all(&self.db)
+        .await
+        else {
+            println!("Failed to get expiring claims");
+            return;
+        };
+
+        if response.is_empty() {
+            return;
+        }
+
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        let mut warnings = vec![];
+        let mut freed = vec![];
+
+        for record in response {
+            let idle_expiry = record.claim_idle_days.map(|days| record.timestamp.max(record.last_update.unwrap_or_default()) + days * 24 * 60 * 60);
+            let Some(expires) = [record.claim_deadline, idle_expiry].into_iter().flatten().min() else {
+                continue;
+            };
+
+            if expires <= current_time {
+                // Expiry isn't a voluntary release, so it doesn't go into released_claims and start the unclaim cooldown
+                if let Err(err) = query!("DELETE FROM claims WHERE id = ?", record.id).execute(&self.db).await {
+                    println!("Failed to expire claim {}: {err}", record.id);
+                    continue;
+                }
+
+                self.audit(
+                    AuditEvent::new(UserId::new(record.snowflake as u64), ClaimCommand::NAME, "Claim expired")
+                        .world(&record.world)
+                        .slot(&record.slot),
+                )
+                .await;
+
+                freed.push(format!("**{}** in {} (claim by <@{}> expired)", record.slot, record.world, record.snowflake));
+            } else if expires - current_time <= EXPIRY_WARNING && record.expiry_warned_at < expires - EXPIRY_WARNING {
+                if query!("UPDATE claims SET expiry_warned_at = ? WHERE id = ?", current_time, record.id).execute(&self.db).await.is_err() {
+                    println!("Failed to mark claim {} as warned", record.id);
+                    continue;
+                }
+
+                warnings.push((
+                    record.snowflake,
+                    format!(
+                        "<@{}> your claim on **{}** in {} expires <t:{expires}:R> unless you start it or post a status update",
+                        record.snowflake, record.slot, record.world
+                    ),
+                ));
+            }
+        }
+
+        if !freed.is_empty() {
+            self.push_needed().await;
+        }
+
+        // Warnings go to the claimant directly and only fall back to the claims channel when they can't be messaged
+        let mut undelivered = vec![];
+        for (snowflake, warning) in warnings {
+            if UserId::new(snowflake as u64).direct_message(&cache_http, CreateMessage::new().content(&warning)).await.is_err() {
+                undelivered.push(warning);
+            }
+        }
+
+        if undelivered.is_empty() && freed.is_empty() {
+            return;
+        }
+
+        let Some(claims_channel) = Bot::claims_channel(&cache_http).await else {
+            println!("Failed to get claims channel");
+            return;
+        };
+
+        for warning in undelivered {
+            let _ = claims_channel.send_message(&cache_http, CreateMessage::new().content(warning)).await;
+        }
+
+        if !freed.is_empty() {
+            let _ = claims_channel
+                .send_message(
+                    &cache_http,
+                    CreateMessage::new().content(format!("The following slots are available again. Use `/claim` to claim them.\n{}", freed.join("\n"))),
+                )
+                .await;
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use serenity::{
+    all::{CacheHttp, ChannelId, Colour, CreateEmbed, CreateEmbedFooter, EditMessage, HttpError, MessageId, Timestamp},
+    Error,
+};
+use sqlx::query;
+
+use crate::Bot;
+
+const MAX_WORLDS: usize = 25;
+const PROGRESS_BAR_LENGTH: i64 = 12;
+const UNKNOWN_CHANNEL: isize = 10003;
+const UNKNOWN_MESSAGE: isize = 10008;
+
+impl Bot {
+    pub fn live_board_needed(&self) {
+        if let Some(mut guard) = self.pending_board.lock() {
+            *guard = true;
+        } else {
+            println!("Failed to aquire pending board lock");
+        }
+    }
+
+    pub async fn update_live_boards(&self, cache_http: impl CacheHttp) {
+        let Ok(boards) = query!("SELECT id, channel, message FROM live_boards").fetch_all(&self.db).await else {
+            println!("Failed to get live boards");
+            return;
+        };
+
+        if boards.is_empty() {
+            return;
+        }
+
+        if let Some(mut guard) = self.pending_board.lock() {
+            if !*guard {
+                return;
+            }
+
+            *guard = false;
+        } else {
+            println!("Failed to aquire pending board lock");
+            return;
+        }
+
+        // Anything that leaves a board stale sets the flag again so the next tick retries
+        let Some(embed) = self.live_board_embed().await else {
+            println!("Failed to build live board");
+            self.live_board_needed();
+            return;
+        };
+
+        for board in boards {
+            if let Err(err) = ChannelId::new(board.channel as u64)
+                .edit_message(&cache_http, MessageId::new(board.message as u64), EditMessage::new().embed(embed.clone()))
+                .await
+            {
+                if is_gone(&err) {
+                    println!("Live board {} was deleted, removing it", board.id);
+                    if let Err(err) = query!("DELETE FROM live_boards WHERE id = ?", board.id).execute(&self.db).await {
+                        println!("Failed to remove live board {}: {err}", board.id);
+                    }
+                } else {
+                    println!("Failed to edit live board {}: {err}", board.id);
+                    self.live_board_needed();
+                }
+            }
+        }
+    }
+
+    pub async fn live_board_embed(&self) -> Option<CreateEmbed> {
+        let response = query!(
+            "SELECT worlds_overview.name AS \"name!\", worlds_overview.reality AS \"reality?\", unclaimed, unstarted, in_progress, goal, all_checks, done,
+            (SELECT SUM(checks) FROM tracked_slots WHERE tracked_slots.world = worlds_overview.id) AS \"checks!: i64\",
+            (SELECT SUM(checks_total) FROM tracked_slots WHERE tracked_slots.world = worlds_overview.id) AS \"checks_total!: i64\"
+            FROM worlds_overview ORDER BY worlds_overview.id"
+        )
+        .fetch_all(&self.db)
+        .await
+        .ok()?;
+
+        let world_count = response.len();
+        let fields = response.into_iter().take(MAX_WORLDS).map(|record| {
+            let mut counts = vec![];
+
+            for (label, count) in [
+                ("Unclaimed", record.unclaimed),
+                ("Unstarted", record.unstarted),
+                ("In progress", record.in_progress),
+                ("Goal", record.goal),
+                ("All checks", record.all_checks),
+                ("Done", record.done),
+            ] {
+                if count > 0 {
+                    counts.push(format!("{label}: {count}"));
+                }
+            }
+
+            (
+                format!("{}{}", record.name, if let Some(reality) = record.reality { format!(" [{reality}]") } else { String::new() }),
+                format!("{}\n{}", progress_bar(record.checks, record.checks_total), counts.join(" | ")),
+                false,
+            )
+        });
+
+        let embed = CreateEmbed::new()
+            .title("Live board")
+            .colour(Colour::DARK_PURPLE)
+            .fields(fields)
+            .timestamp(Timestamp::now())
+            .footer(CreateEmbedFooter::new(if world_count > MAX_WORLDS {
+                format!("{} more worlds not shown. Last updated", world_count - MAX_WORLDS)
+            } else {
+                String::from("Last updated")
+            }));
+
+        Some(if world_count == 0 { embed.description("*No active worlds*") } else { embed })
+    }
+}
+
+// Discord reports the board's message or channel as unknown once it has been deleted
+fn is_gone(err: &Error) -> bool {
+    matches!(err, Error::Http(HttpError::UnsuccessfulRequest(response)) if [UNKNOWN_CHANNEL, UNKNOWN_MESSAGE].contains(&response.error.code))
+}
+
+fn progress_bar(checks: i64, checks_total: i64) -> String {
+    if checks_total <= 0 {
+        return String::from("*No checks*");
+    }
+
+    let filled = (checks * PROGRESS_BAR_LENGTH / checks_total).clamp(0, PROGRESS_BAR_LENGTH);
+
+    format!(
+        "`{}{}` {}% ({checks}/{checks_total})",
+        "█".repeat(filled as usize),
+        "░".repeat((PROGRESS_BAR_LENGTH - filled) as usize),
+        checks * 100 / checks_total
+    )
+}
@@ -1,34 +1,30 @@
+// Newer nightlies warn about features that are enabled ahead of use
+#![allow(unused_features)]
 #![feature(iter_next_chunk)]
 #![feature(iter_array_chunks)]
 
+mod audit;
 mod autocomplete;
 mod channels;
 mod commands;
+mod custom_id;
+mod discord;
+mod expiry;
+mod live_board;
+mod options;
 mod paginate;
+mod policy;
 mod scrape;
+mod sheet_store;
 mod sheets;
+#[cfg(test)]
+mod tests;
+mod undo;
 mod util;
 
-use std::{
-    env,
-    fs::{File, OpenOptions},
-    io::Write,
-    sync::Arc,
-    time::Duration,
-};
+use std::{env, sync::Arc, time::Duration};
 
-use chrono::Local;
 use dotenvy::from_filename_override;
-use google_sheets4::{
-    hyper_util::{
-        client::legacy::{connect::HttpConnector, Client as SheetsClient},
-        rt::TokioExecutor,
-    },
-    yup_oauth2::{read_service_account_key, ServiceAccountAuthenticator},
-    Sheets,
-};
-use http_body_util::combinators::BoxBody;
-use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 use rustls::{
     crypto::{aws_lc_rs, CryptoProvider},
     lock::Mutex,
@@ -44,21 +40,26 @@ use sqlx::{
 };
 use tokio::{spawn, time::interval};
 
-use crate::commands::interaction_create;
+use crate::{
+    commands::interaction_create,
+    paginate::PageCache,
+    sheet_store::SheetBackend,
+    sheets::{load_layout, SheetSnapshot, Table},
+};
 
-const MAX_REALITIES: usize = 3;
-const NO_REALITY_CLAIMS: i64 = 2;
 const UNSPENT_POINTS_LIMIT: i64 = 0;
-const LOG_PATH: &str = "bot.log";
 
 struct Bot {
     db: SqlitePool,
     admins: Vec<UserId>,
     privileged: Vec<UserId>,
-    sheets: Sheets<HttpsConnector<HttpConnector>>,
+    sheets: SheetBackend,
     latest_push: Arc<Mutex<u64>>,
     pending_push: Arc<Mutex<bool>>,
-    log: Arc<Mutex<File>>,
+    sheet_layout: Vec<Table>,
+    sheet_snapshot: Arc<Mutex<Option<SheetSnapshot>>>,
+    pending_board: Arc<Mutex<bool>>,
+    page_cache: Arc<Mutex<PageCache>>,
 }
 
 struct Player {
@@ -67,13 +68,6 @@ struct Player {
     pub unspent_points: i64,
 }
 
-#[derive(Clone, Copy)]
-struct Reality {
-    pub id: i64,
-    pub max_claims: i64,
-    pub external: bool,
-}
-
 impl Bot {
     async fn get_player(&self, snowflake: i64, name: &str) -> Option<Player> {
         if let Ok(response) = query_as!(Player, "SELECT id, name, unspent_points FROM players WHERE snowflake = ? LIMIT 1", snowflake)
@@ -93,105 +87,6 @@ impl Bot {
             })
         }
     }
-
-    fn log(&self, content: &str) {
-        let timestamp = Local::now();
-
-        if let Some(mut guard) = self.log.lock() {
-            if writeln!(guard, "[{timestamp}] {content}").is_err() {
-                println!("[FAILED LOG WRITE] [{timestamp}] {content}");
-            }
-        } else {
-            println!("[FAILED TO AQUIRE LOG FILE LOCK] [{timestamp}] {content}");
-        }
-    }
-
-    async fn can_claim_slot(&self, player: i64, slot: i64) -> Result<(), &'static str> {
-        if let Ok(response) = query!(
-            "SELECT realities.id, realities.max_claims, external FROM tracked_slots LEFT JOIN tracked_worlds ON tracked_worlds.id = tracked_slots.world LEFT JOIN realities ON realities.id = tracked_worlds.reality WHERE tracked_slots.id = ?",
-            slot
-        )
-        .fetch_optional(&self.db)
-        .await
-        {
-            self.can_claim_in_reality(
-                player,
-                response.map(|record| Reality {
-                    id: record.id,
-                    max_claims: record.max_claims.unwrap_or(NO_REALITY_CLAIMS),
-                    external: record.external.unwrap_or_default() > 0
-                }),
-            )
-            .await
-        } else {
-            Err("Failed to get reality")
-        }
-    }
-
-    async fn can_preclaim_slot(&self, player: i64, slot: i64) -> Result<(), &'static str> {
-        if let Ok(response) = query!(
-            "SELECT realities.id, realities.max_claims, external FROM slots LEFT JOIN worlds ON worlds.id = slots.world LEFT JOIN realities ON realities.id = worlds.reality WHERE slots.id = ?",
-            slot
-        )
-        .fetch_optional(&self.db)
-        .await
-        {
-            self.can_claim_in_reality(
-                player,
-                response.map(|record| Reality {
-                    id: record.id,
-                    max_claims: record.max_claims.unwrap_or(NO_REALITY_CLAIMS),
-                    external: record.external.unwrap_or_default() > 0,
-                }),
-            )
-            .await
-        } else {
-            Err("Failed to get reality")
-        }
-    }
-
-    async fn can_claim_in_reality(&self, player: i64, reality: Option<Reality>) -> Result<(), &'static str> {
-        let (max_claims, current_claims) = if let Some(reality) = reality {
-            if !reality.external {
-                let realities: Vec<_> = if let Ok(response) = query!("SELECT realities FROM current_realities_claims WHERE player = ?", player).fetch_all(&self.db).await {
-                    response.into_iter().map(|record| record.realities).collect()
-                } else {
-                    return Err("Failed to get current realities");
-                };
-
-                if !realities.contains(&reality.id) && realities.len() >= MAX_REALITIES {
-                    return Err("You cannot join more realities");
-                }
-            }
-
-            (
-                reality.max_claims,
-                if let Ok(response) = query!("SELECT claims FROM current_claims WHERE player = ? AND reality = ?", player, reality.id)
-                    .fetch_optional(&self.db)
-                    .await
-                {
-                    response.map(|record| record.claims).unwrap_or(0)
-                } else {
-                    return Err("Failed to get current claims");
-                },
-            )
-        } else {
-            (
-                NO_REALITY_CLAIMS,
-                if let Ok(response) = query!("SELECT claims FROM current_claims WHERE player = ? AND reality IS NULL", player).fetch_optional(&self.db).await {
-                    response.map(|record| record.claims).unwrap_or(0)
-                } else {
-                    return Err("Failed to get current claims");
-                },
-            )
-        };
-
-        if current_claims >= max_claims {
-            return Err("No available claim");
-        }
-
-        Ok(())
-    }
 }
 
 #[async_trait]
@@ -209,8 +104,6 @@ async fn main() {
 
     let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
 
-    let log = OpenOptions::new().append(true).create(true).open(LOG_PATH).unwrap();
-
     let db = SqlitePoolOptions::new()
         .max_connections(5)
         .connect_with(SqliteConnectOptions::new().filename("db.sqlite").create_if_missing(true))
@@ -228,33 +121,7 @@ async fn main() {
     ]);
 
     let _ = CryptoProvider::install_default(aws_lc_rs::default_provider());
-    let client: SheetsClient<_, BoxBody<google_sheets4::hyper::body::Bytes, google_sheets4::hyper::Error>> = SheetsClient::builder(TokioExecutor::new()).build(
-        HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .expect("Failed to set tls config")
-            .https_only()
-            .enable_http1()
-            .enable_http2()
-            .build(),
-    );
-    let secret = read_service_account_key("sheets_key.json").await.expect("Failed to find sheets api key file");
-    let auth = ServiceAccountAuthenticator::with_client(
-        secret,
-        SheetsClient::builder(TokioExecutor::new()).build(
-            HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .expect("Failed to set tls config")
-                .https_only()
-                .enable_http1()
-                .enable_http2()
-                .build(),
-        ),
-    )
-    .build()
-    .await
-    .expect("Failed to create an authenticator");
-
-    let sheets = Sheets::new(client, auth);
+    let sheets = SheetBackend::from_env().await;
 
     let bot = Box::new(Bot {
         db,
@@ -263,7 +130,10 @@ async fn main() {
         sheets,
         latest_push: Arc::new(Mutex::new(0)),
         pending_push: Arc::new(Mutex::new(false)),
-        log: Arc::new(Mutex::new(log)),
+        sheet_layout: load_layout(),
+        sheet_snapshot: Arc::new(Mutex::new(None)),
+        pending_board: Arc::new(Mutex::new(true)),
+        page_cache: Arc::new(Mutex::new(PageCache::new())),
     });
 
     let bot: &'static Bot = Box::leak(bot);
@@ -276,9 +146,35 @@ async fn main() {
         }
     });
 
+    spawn(async {
+        let mut interval = interval(Duration::from_secs(60 * 10));
+        loop {
+            interval.tick().await;
+            bot.expire_claim_grants().await;
+        }
+    });
+
     let intents = GatewayIntents::empty();
     let mut client = DiscordClient::builder(&token, intents).event_handler(bot).await.expect("Error creating client");
 
+    let http = client.http.clone();
+    spawn(async move {
+        let mut interval = interval(Duration::from_secs(60 * 10));
+        loop {
+            interval.tick().await;
+            bot.expire_claims(&http).await;
+        }
+    });
+
+    let http = client.http.clone();
+    spawn(async move {
+        let mut interval = interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            bot.update_live_boards(&http).await;
+        }
+    });
+
     if let Err(err) = client.start().await {
         println!("Client error: {err:?}");
     }
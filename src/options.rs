@@ -0,0 +1,164 @@
+use serenity::all::{Attachment, ChannelId, CommandOptionType, GuildId, ResolvedValue, User};
+
+pub enum OptionError {
+    Missing,
+    Invalid,
+}
+
+pub trait OptionValue: Sized {
+    const KIND: CommandOptionType;
+    const REQUIRED: bool = true;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self>;
+
+    fn from_option(value: Option<&ResolvedValue>) -> Result<Self, OptionError> {
+        Self::from_value(value.ok_or(OptionError::Missing)?).ok_or(OptionError::Invalid)
+    }
+}
+
+impl<T: OptionValue> OptionValue for Option<T> {
+    const KIND: CommandOptionType = T::KIND;
+    const REQUIRED: bool = false;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        T::from_value(value).map(Some)
+    }
+
+    fn from_option(value: Option<&ResolvedValue>) -> Result<Self, OptionError> {
+        match value {
+            Some(value) => Self::from_value(value).ok_or(OptionError::Invalid),
+            None => Ok(None),
+        }
+    }
+}
+
+impl OptionValue for String {
+    const KIND: CommandOptionType = CommandOptionType::String;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        match value {
+            ResolvedValue::String(value) if !value.trim().is_empty() => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for i64 {
+    const KIND: CommandOptionType = CommandOptionType::Integer;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        match value {
+            ResolvedValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for bool {
+    const KIND: CommandOptionType = CommandOptionType::Boolean;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        match value {
+            ResolvedValue::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for User {
+    const KIND: CommandOptionType = CommandOptionType::User;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        match value {
+            ResolvedValue::User(value, _) => Some((*value).clone()),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for ChannelId {
+    const KIND: CommandOptionType = CommandOptionType::Channel;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        match value {
+            ResolvedValue::Channel(value) => Some(value.id),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for Attachment {
+    const KIND: CommandOptionType = CommandOptionType::Attachment;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        match value {
+            ResolvedValue::Attachment(value) => Some((*value).clone()),
+            _ => None,
+        }
+    }
+}
+
+// Discord has no guild option type, so guild ids are taken as strings
+impl OptionValue for GuildId {
+    const KIND: CommandOptionType = CommandOptionType::String;
+
+    fn from_value(value: &ResolvedValue) -> Option<Self> {
+        match value {
+            ResolvedValue::String(value) => value.trim().parse::<u64>().ok().filter(|value| *value != 0).map(GuildId::new),
+            _ => None,
+        }
+    }
+}
+
+// Declares a struct of slash command options. The field types decide the option kind and whether it is required, and any builder calls after the name and description are applied to the option
+macro_rules! command_options {
+    ($vis:vis struct $options:ident {
+        $($field:ident: $ty:ty = ($name:literal, $description:literal) $(.$method:ident($($arg:expr),* $(,)?))*),* $(,)?
+    }) => {
+        $vis struct $options {
+            $($field: $ty),*
+        }
+
+        impl $options {
+            #[allow(dead_code)]
+            fn options() -> Vec<serenity::all::CreateCommandOption> {
+                vec![$(serenity::all::CreateCommandOption::new(<$ty as $crate::options::OptionValue>::KIND, $name, $description)
+                    .required(<$ty as $crate::options::OptionValue>::REQUIRED)
+                    $(.$method($($arg),*))*),*]
+            }
+
+            #[allow(dead_code)]
+            fn add_options(command: serenity::all::CreateCommand) -> serenity::all::CreateCommand {
+                Self::options().into_iter().fold(command, |command, option| command.add_option(option))
+            }
+
+            #[allow(dead_code)]
+            fn add_sub_options(subcommand: serenity::all::CreateCommandOption) -> serenity::all::CreateCommandOption {
+                Self::options().into_iter().fold(subcommand, |subcommand, option| subcommand.add_sub_option(option))
+            }
+
+            #[allow(dead_code)]
+            async fn parse(ctx: &impl $crate::discord::Discord, command: &serenity::all::CommandInteraction) -> Option<Self> {
+                Self::parse_from(ctx, command, &command.data.options()).await
+            }
+
+            async fn parse_from(ctx: &impl $crate::discord::Discord, command: &serenity::all::CommandInteraction, options: &[serenity::all::ResolvedOption<'_>]) -> Option<Self> {
+                $(let $field = match <$ty as $crate::options::OptionValue>::from_option(options.iter().find(|option| option.name == $name).map(|option| &option.value)) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        $crate::util::SimpleReply::simple_reply(command, ctx, match err {
+                            $crate::options::OptionError::Missing => concat!("The `", $name, "` option is required"),
+                            $crate::options::OptionError::Invalid => concat!("The `", $name, "` option has an invalid value"),
+                        })
+                        .await;
+                        return None;
+                    }
+                };)*
+
+                Some(Self { $($field),* })
+            }
+        }
+    };
+}
+
+pub(crate) use command_options;
@@ -1,27 +1,85 @@
-use std::fmt::Display;
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{from_str, to_string};
 use serenity::{
-    all::{ButtonStyle, ComponentInteraction, Context, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage, EditInteractionResponse},
+    all::{
+        ActionRowComponent, ButtonStyle, ComponentInteraction, ComponentInteractionDataKind, Context, CreateActionRow, CreateButton, CreateEmbed, CreateInputText, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateModal, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse, InputTextStyle, MessageId, ModalInteraction,
+    },
     futures::future::join_all,
 };
+use sqlx::query;
+
+use crate::{
+    commands::Command,
+    custom_id::{CustomId, Fields},
+    util::SimpleReply,
+    Bot,
+};
+
+const CACHE_LIFETIME: u64 = 5 * 60;
+// Saved filters are only needed while their message can still be paged, older ones are deleted whenever a new one is saved
+const FILTER_LIFETIME: i64 = 24 * 60 * 60;
+
+pub type PageCache = HashMap<(MessageId, String), (u64, Arc<dyn Any + Send + Sync>)>;
+
+impl Bot {
+    // Filters don't fit in custom ids, so commands save them here and page with the id instead
+    pub async fn save_filter(&self, command: &str, filter: &impl Serialize) -> Option<i64> {
+        if let Err(err) = query!("DELETE FROM saved_filters WHERE timestamp < strftime('%s', 'now') - ?", FILTER_LIFETIME).execute(&self.db).await {
+            println!("Failed to delete old filters: {err}");
+        }
+
+        let filter = to_string(filter).ok()?;
+        query!("INSERT INTO saved_filters (command, filter) VALUES (?, ?) RETURNING id", command, filter)
+            .fetch_one(&self.db)
+            .await
+            .ok()
+            .map(|record| record.id)
+    }
+
+    pub async fn saved_filter<T: DeserializeOwned>(&self, command: &str, id: i64) -> Option<T> {
+        let record = query!("SELECT filter FROM saved_filters WHERE id = ? AND command = ? LIMIT 1", id, command)
+            .fetch_optional(&self.db)
+            .await
+            .ok()??;
+        from_str(&record.filter).ok()
+    }
+}
 
-use crate::{commands::Command, util::SimpleReply, Bot};
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct View {
+    pub sort: usize,
+    pub filter: usize,
+}
 
 pub trait Paginate<Container, Item, Details, ExtraPageDetails = ()>
 where
     Self: Command,
-    Container: PageContainer<Item, Details, ExtraPageDetails>,
+    Container: PageContainer<Item, Details, ExtraPageDetails> + Send + Sync + 'static,
     Item: PageItem<Details, ExtraPageDetails>,
     Details: PageDetails,
     ExtraPageDetails: Copy,
 {
     const PAGE_SIZE: usize = 25;
+    const SORTS: &'static [&'static str] = &[];
+    const FILTERS: &'static [&'static str] = &[];
 
-    async fn get_containers(bot: &Bot, extra: ExtraPageDetails) -> Vec<Container>;
-    fn additional_components(_components: &mut Vec<CreateActionRow>, _container: &Container, _details: &[Details]) {}
+    async fn get_containers(bot: &Bot, extra: ExtraPageDetails, view: View) -> Vec<Container>;
+    fn additional_components(_components: &mut Vec<CreateActionRow>, _container: &Container, _details: &[Details], _page: usize, _view: View) {}
     async fn additional_fields(_bot: &Bot, _fields: &mut Vec<(String, String, bool)>, _extra: ExtraPageDetails) {}
-    fn id_prefix(_container: &Container, _extra: ExtraPageDetails) -> impl Display {
-        Self::NAME
+    fn id_prefix(_extra: ExtraPageDetails) -> CustomId {
+        CustomId::new(Self::NAME)
+    }
+
+    async fn details_for_page(bot: &Bot, items: &[Item], extra: ExtraPageDetails) -> Vec<Details> {
+        join_all(items.iter().map(|item| item.details(bot, extra))).await.into_iter().flatten().collect()
     }
 
     fn page_count(containers: &[Container]) -> usize {
@@ -51,27 +109,66 @@ where
         Some((current_container, &current_items[current_start..end]))
     }
 
-    async fn build_embed(bot: &Bot, container: &Container, details: &[Details], page: usize, page_count: usize, extra: ExtraPageDetails) -> Response {
+    fn view_components(components: &mut Vec<CreateActionRow>, prefix: &CustomId, view: View) {
+        if Self::SORTS.len() > 1 {
+            components.push(CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(
+                    PageAction::Sort { filter: view.filter }.encode(prefix),
+                    CreateSelectMenuKind::String {
+                        options: Self::SORTS
+                            .iter()
+                            .enumerate()
+                            .map(|(i, label)| CreateSelectMenuOption::new(*label, i.to_string()).default_selection(i == view.sort))
+                            .collect(),
+                    },
+                )
+                .placeholder("Sort by")
+                .min_values(1)
+                .max_values(1),
+            ));
+        }
+
+        if Self::FILTERS.len() > 1 {
+            components.push(CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(
+                    PageAction::Filter { sort: view.sort }.encode(prefix),
+                    CreateSelectMenuKind::String {
+                        options: Self::FILTERS
+                            .iter()
+                            .enumerate()
+                            .map(|(i, label)| CreateSelectMenuOption::new(*label, i.to_string()).default_selection(i == view.filter))
+                            .collect(),
+                    },
+                )
+                .placeholder("Filter")
+                .min_values(1)
+                .max_values(1),
+            ));
+        }
+    }
+
+    async fn build_embed(bot: &Bot, container: &Container, details: &[Details], page: usize, page_count: usize, extra: ExtraPageDetails, view: View) -> Response {
+        let prefix = Self::id_prefix(extra);
         let mut components = vec![];
 
         if page_count > 1 {
             components.push(CreateActionRow::Buttons(vec![
-                CreateButton::new(format!("{}-page-{}", Self::id_prefix(container, extra), page - 1))
+                CreateButton::new(PageAction::Page { page: page.saturating_sub(1), view }.encode(&prefix))
                     .disabled(page == 0)
                     .style(ButtonStyle::Secondary)
                     .label("← Prev"),
-                CreateButton::new(format!("{}-page-count", Self::id_prefix(container, extra)))
-                    .disabled(true)
+                CreateButton::new(PageAction::Jump { view }.encode(&prefix))
                     .style(ButtonStyle::Secondary)
                     .label(format!("Page {} of {page_count}", page + 1)),
-                CreateButton::new(format!("{}-page-{}", Self::id_prefix(container, extra), page + 1))
+                CreateButton::new(PageAction::Page { page: page + 1, view }.encode(&prefix))
                     .disabled(page + 1 == page_count)
                     .style(ButtonStyle::Secondary)
                     .label("Next →"),
             ]));
         }
 
-        Self::additional_components(&mut components, container, details);
+        Self::view_components(&mut components, &prefix, view);
+        Self::additional_components(&mut components, container, details, page, view);
 
         let mut fields = vec![];
         Self::additional_fields(bot, &mut fields, extra).await;
@@ -82,55 +179,199 @@ where
     }
 
     async fn first_page(bot: &Bot, extra: ExtraPageDetails) -> Option<Response> {
-        let containers = Self::get_containers(bot, extra).await;
-        let first_container = containers.first()?;
-        let page_count = Self::page_count(&containers);
-        let details: Vec<_> = join_all(first_container.items().iter().take(Self::PAGE_SIZE).map(|item| item.details(bot, extra)))
-            .await
-            .into_iter()
-            .flatten()
-            .collect();
+        Self::page(bot, extra, View::default(), 0).await
+    }
 
-        Some(Self::build_embed(bot, first_container, &details, 0, page_count, extra).await)
+    async fn page(bot: &Bot, extra: ExtraPageDetails, view: View, page: usize) -> Option<Response> {
+        let containers = Self::get_containers(bot, extra, view).await;
+        Self::render_page(bot, &containers, extra, view, page).await
     }
 
-    async fn page(bot: &Bot, extra: ExtraPageDetails, page: usize) -> Option<Response> {
-        let containers = Self::get_containers(bot, extra).await;
-        let page_count = Self::page_count(&containers);
+    async fn render_page(bot: &Bot, containers: &[Container], extra: ExtraPageDetails, view: View, page: usize) -> Option<Response> {
+        if containers.is_empty() {
+            return None;
+        }
+
+        let page_count = Self::page_count(containers);
 
         if page >= page_count {
             return Some(Response::new().content("Invalid page"));
         }
 
-        let Some((container, items)) = Self::get_page(&containers, page) else {
+        let Some((container, items)) = Self::get_page(containers, page) else {
             return Some(Response::new().content("Invalid page"));
         };
 
-        let details: Vec<_> = join_all(items.iter().map(|item| item.details(bot, extra))).await.into_iter().flatten().collect();
+        let details = Self::details_for_page(bot, items, extra).await;
 
-        Some(Self::build_embed(bot, container, &details, page, page_count, extra).await)
+        Some(Self::build_embed(bot, container, &details, page, page_count, extra, view).await)
     }
 
-    async fn try_handle_interaction(bot: &Bot, ctx: &Context, interaction: &ComponentInteraction, id: &str, extra: ExtraPageDetails) -> bool {
-        let Some((_, rest)) = id.split_once("page-") else {
+    async fn cached_containers(bot: &Bot, key: (MessageId, String), extra: ExtraPageDetails, view: View) -> Arc<Vec<Container>> {
+        let current_secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+
+        if let Some(mut cache) = bot.page_cache.lock() {
+            cache.retain(|_, (timestamp, _)| current_secs.saturating_sub(*timestamp) < CACHE_LIFETIME);
+
+            if let Some(containers) = cache.get(&key).and_then(|(_, containers)| containers.clone().downcast::<Vec<Container>>().ok()) {
+                return containers;
+            }
+        } else {
+            println!("Failed to aquire page cache lock");
+        }
+
+        let containers = Arc::new(Self::get_containers(bot, extra, view).await);
+
+        if let Some(mut cache) = bot.page_cache.lock() {
+            cache.insert(key, (current_secs, containers.clone()));
+        }
+
+        containers
+    }
+
+    async fn navigate(bot: &Bot, message: MessageId, prefix: &CustomId, extra: ExtraPageDetails, view: View, page: usize) -> Response {
+        let view = View {
+            sort: if view.sort < Self::SORTS.len() { view.sort } else { 0 },
+            filter: if view.filter < Self::FILTERS.len() { view.filter } else { 0 },
+        };
+        let containers = Self::cached_containers(bot, (message, format!("{prefix}-{}-{}", view.sort, view.filter)), extra, view).await;
+
+        if containers.is_empty() {
+            let mut components = vec![];
+            Self::view_components(&mut components, prefix, view);
+            return Response::new().content("Nothing matches this view").components(components);
+        }
+
+        let page = page.min(Self::page_count(&containers).saturating_sub(1));
+        Self::render_page(bot, &containers, extra, view, page)
+            .await
+            .unwrap_or_else(|| Response::new().content("There was an error handling your interraction"))
+    }
+
+    async fn try_handle_interaction(bot: &Bot, ctx: &Context, interaction: &ComponentInteraction, mut fields: Fields<'_>, extra: ExtraPageDetails) -> bool {
+        let prefix = fields.prefix();
+        let Some(action) = PageAction::decode(&mut fields) else {
             return false;
         };
+        let message = interaction.message.id;
+        let selected = if let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind {
+            values.first().and_then(|value| value.parse::<usize>().ok())
+        } else {
+            None
+        };
+
+        let response = match action {
+            PageAction::Page { page, view } => Self::navigate(bot, message, &prefix, extra, view, page).await,
+            PageAction::Sort { filter } => {
+                let Some(sort) = selected else {
+                    interaction.simple_reply(ctx, "Malformed sort selection").await;
+                    return true;
+                };
+
+                Self::navigate(bot, message, &prefix, extra, View { sort, filter }, 0).await
+            }
+            PageAction::Filter { sort } => {
+                let Some(filter) = selected else {
+                    interaction.simple_reply(ctx, "Malformed filter selection").await;
+                    return true;
+                };
 
-        if let Ok(new_page) = rest.parse::<usize>() {
-            let Some(response) = Self::page(bot, extra, new_page).await else {
-                interaction.simple_reply(ctx, "There was an error handling your interraction").await;
+                Self::navigate(bot, message, &prefix, extra, View { sort, filter }, 0).await
+            }
+            PageAction::Jump { view } => {
+                let modal = CreateModal::new(PageAction::Goto { view }.encode(&prefix), "Jump to page").components(vec![CreateActionRow::InputText(
+                    CreateInputText::new(InputTextStyle::Short, "Page", "page").required(true).max_length(6),
+                )]);
+
+                let _ = interaction.create_response(ctx, CreateInteractionResponse::Modal(modal)).await;
                 return true;
-            };
+            }
+            PageAction::Goto { .. } => return false,
+        };
 
-            let _ = interaction.create_response(ctx, CreateInteractionResponse::UpdateMessage(response.into())).await;
-        } else {
-            interaction.simple_reply(ctx, "Malformed page number").await;
-        }
+        let _ = interaction.create_response(ctx, CreateInteractionResponse::UpdateMessage(response.into())).await;
+        true
+    }
+
+    async fn try_handle_modal(bot: &Bot, ctx: &Context, interaction: &ModalInteraction, mut fields: Fields<'_>, extra: ExtraPageDetails) -> bool {
+        let prefix = fields.prefix();
+        let Some(PageAction::Goto { view }) = PageAction::decode(&mut fields) else {
+            return false;
+        };
+        let Some(message) = &interaction.message else {
+            interaction.simple_reply(ctx, "This page is no longer available").await;
+            return true;
+        };
 
+        let page = interaction
+            .data
+            .components
+            .iter()
+            .flat_map(|row| &row.components)
+            .find_map(|component| match component {
+                ActionRowComponent::InputText(input) if input.custom_id == "page" => input.value.as_deref(),
+                _ => None,
+            })
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .filter(|page| *page > 0);
+
+        let Some(page) = page else {
+            interaction.simple_reply(ctx, "Pages must be a positive number").await;
+            return true;
+        };
+
+        let response = Self::navigate(bot, message.id, &prefix, extra, view, page - 1).await;
+        let _ = interaction.create_response(ctx, CreateInteractionResponse::UpdateMessage(response.into())).await;
         true
     }
 }
 
+enum PageAction {
+    Page { page: usize, view: View },
+    Jump { view: View },
+    Goto { view: View },
+    Sort { filter: usize },
+    Filter { sort: usize },
+}
+
+impl PageAction {
+    fn encode(&self, prefix: &CustomId) -> String {
+        let id = prefix.clone();
+
+        match self {
+            PageAction::Page { page, view } => id.push("page").push(page).push(view.sort).push(view.filter),
+            PageAction::Jump { view } => id.push("jump").push(view.sort).push(view.filter),
+            PageAction::Goto { view } => id.push("goto").push(view.sort).push(view.filter),
+            PageAction::Sort { filter } => id.push("sort").push(filter),
+            PageAction::Filter { sort } => id.push("filter").push(sort),
+        }
+        .build()
+    }
+
+    fn decode(fields: &mut Fields) -> Option<Self> {
+        match fields.next_str()? {
+            "page" => Some(PageAction::Page {
+                page: fields.next()?,
+                view: View::decode(fields)?,
+            }),
+            "jump" => Some(PageAction::Jump { view: View::decode(fields)? }),
+            "goto" => Some(PageAction::Goto { view: View::decode(fields)? }),
+            "sort" => Some(PageAction::Sort { filter: fields.next()? }),
+            "filter" => Some(PageAction::Filter { sort: fields.next()? }),
+            _ => None,
+        }
+    }
+}
+
+impl View {
+    pub fn decode(fields: &mut Fields) -> Option<Self> {
+        Some(View {
+            sort: fields.next()?,
+            filter: fields.next()?,
+        })
+    }
+}
+
 pub trait PageContainer<Item: PageItem<Details, ExtraPageDetails>, Details: PageDetails, ExtraPageDetails = ()> {
     fn items(&self) -> &[Item];
     fn page_setup(&self) -> CreateEmbed {
@@ -146,6 +387,16 @@ pub trait PageDetails {
     fn field(&self) -> (String, String, bool);
 }
 
+impl Bot {
+    pub fn clear_page_cache(&self, message: MessageId) {
+        if let Some(mut cache) = self.page_cache.lock() {
+            cache.retain(|(cached_message, _), _| *cached_message != message);
+        } else {
+            println!("Failed to aquire page cache lock");
+        }
+    }
+}
+
 pub struct Response {
     content: Option<String>,
     embeds: Vec<CreateEmbed>,
@@ -179,24 +430,19 @@ impl Response {
 
 impl From<Response> for CreateInteractionResponseMessage {
     fn from(value: Response) -> Self {
-        if let Some(content) = value.content {
-            CreateInteractionResponseMessage::new()
-                .ephemeral(true)
-                .content(content)
-                .embeds(value.embeds)
-                .components(value.components)
-        } else {
-            CreateInteractionResponseMessage::new().ephemeral(true).embeds(value.embeds).components(value.components)
-        }
+        CreateInteractionResponseMessage::new()
+            .ephemeral(true)
+            .content(value.content.unwrap_or_default())
+            .embeds(value.embeds)
+            .components(value.components)
     }
 }
 
 impl From<Response> for EditInteractionResponse {
     fn from(value: Response) -> Self {
-        if let Some(content) = value.content {
-            EditInteractionResponse::new().content(content).embeds(value.embeds).components(value.components)
-        } else {
-            EditInteractionResponse::new().embeds(value.embeds).components(value.components)
-        }
+        EditInteractionResponse::new()
+            .content(value.content.unwrap_or_default())
+            .embeds(value.embeds)
+            .components(value.components)
     }
 }
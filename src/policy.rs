@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sqlx::{query, query_as};
+
+use crate::{commands::track_world::split_game_str, Bot};
+
+pub const MAX_REALITIES: usize = 3;
+pub const NO_REALITY_CLAIMS: i64 = 2;
+
+pub struct Reality {
+    pub id: i64,
+    pub name: String,
+    pub max_claims: i64,
+    pub external: bool,
+    pub min_completed: i64,
+    pub unclaim_cooldown: i64,
+    pub points_per_claim: i64,
+}
+
+impl Reality {
+    pub async fn fetch(bot: &Bot, id: i64) -> Option<Self> {
+        query_as!(
+            Reality,
+            "SELECT id, name, max_claims, external AS \"external: bool\", min_completed, unclaim_cooldown, points_per_claim FROM realities WHERE id = ? LIMIT 1",
+            id
+        )
+        .fetch_one(&bot.db)
+        .await
+        .ok()
+    }
+
+    pub fn earned_claims(&self, points: i64) -> i64 {
+        if self.points_per_claim > 0 {
+            points / self.points_per_claim
+        } else {
+            0
+        }
+    }
+}
+
+impl Bot {
+    pub async fn granted_claims(&self, player: i64, reality: i64) -> Result<i64, sqlx::Error> {
+        Ok(query!(
+            "SELECT COALESCE(SUM(claims), 0) AS \"claims!: i64\" FROM claim_grants WHERE player = ? AND reality = ? AND expires > strftime('%s', 'now')",
+            player,
+            reality
+        )
+        .fetch_one(&self.db)
+        .await?
+        .claims)
+    }
+
+    pub async fn expire_claim_grants(&self) {
+        match query!("DELETE FROM claim_grants WHERE expires <= strftime('%s', 'now')").execute(&self.db).await {
+            Ok(response) if response.rows_affected() > 0 => println!("Expired {} claim grants", response.rows_affected()),
+            Ok(_) => (),
+            Err(err) => println!("Failed to expire claim grants: {err}"),
+        }
+    }
+
+    pub async fn can_claim_slot(&self, player: i64, slot: i64) -> Result<(), String> {
+        let Ok(Some(response)) = query!(
+            "SELECT tracked_worlds.reality, games FROM tracked_slots INNER JOIN tracked_worlds ON tracked_worlds.id = tracked_slots.world WHERE tracked_slots.id = ? LIMIT 1",
+            slot
+        )
+        .fetch_optional(&self.db)
+        .await
+        else {
+            return Err(String::from("Failed to get reality"));
+        };
+
+        self.can_claim_in_reality(player, response.reality, &response.games).await
+    }
+
+    pub async fn can_preclaim_slot(&self, player: i64, slot: i64) -> Result<(), String> {
+        let Ok(Some(response)) = query!("SELECT worlds.reality, games FROM slots INNER JOIN worlds ON worlds.id = slots.world WHERE slots.id = ? LIMIT 1", slot)
+            .fetch_optional(&self.db)
+            .await
+        else {
+            return Err(String::from("Failed to get reality"));
+        };
+
+        self.can_claim_in_reality(player, response.reality, &response.games).await
+    }
+
+    async fn can_claim_in_reality(&self, player: i64, reality: Option<i64>, games: &str) -> Result<(), String> {
+        let Some(reality) = reality else {
+            let current_claims = query!("SELECT claims FROM current_claims WHERE player = ? AND reality IS NULL", player)
+                .fetch_optional(&self.db)
+                .await
+                .map_err(|_| "Failed to get current claims")?
+                .map(|record| record.claims)
+                .unwrap_or(0);
+
+            if current_claims >= NO_REALITY_CLAIMS {
+                return Err(format!("No available claim. You already have {current_claims} of {NO_REALITY_CLAIMS} claims outside of realities"));
+            }
+
+            return Ok(());
+        };
+
+        let reality = Reality::fetch(self, reality).await.ok_or("Failed to get reality")?;
+
+        let stats = query!("SELECT points, slots_completed FROM players WHERE id = ? LIMIT 1", player)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|_| "Failed to get player")?;
+
+        if !reality.external {
+            let realities: Vec<_> = query!(
+                "SELECT current_realities.reality FROM current_realities INNER JOIN realities ON realities.id = current_realities.reality WHERE player = ? AND external = 0",
+                player
+            )
+            .fetch_all(&self.db)
+            .await
+            .map_err(|_| "Failed to get current realities")?
+            .into_iter()
+            .filter_map(|record| record.reality)
+            .collect();
+
+            if !realities.contains(&reality.id) && realities.len() >= MAX_REALITIES {
+                return Err(format!("You cannot join more realities. You already have claims in {MAX_REALITIES} realities"));
+            }
+        }
+
+        if reality.unclaim_cooldown > 0 {
+            if let Some(released) = query!("SELECT MAX(timestamp) AS \"timestamp?: i64\" FROM released_claims WHERE player = ? AND reality = ?", player, reality.id)
+                .fetch_one(&self.db)
+                .await
+                .map_err(|_| "Failed to get previous unclaims")?
+                .timestamp
+            {
+                let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+                let available = released + reality.unclaim_cooldown;
+
+                if available > current_time {
+                    return Err(format!("You were recently unclaimed from a slot in {} and can claim there again <t:{available}:R>", reality.name));
+                }
+            }
+        }
+
+        let current_claims = query!("SELECT claims FROM current_claims WHERE player = ? AND reality = ?", player, reality.id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|_| "Failed to get current claims")?
+            .map(|record| record.claims)
+            .unwrap_or(0);
+
+        let earned_claims = reality.earned_claims(stats.points);
+        let granted_claims = self.granted_claims(player, reality.id).await.map_err(|_| "Failed to get granted claims")?;
+        let allowance = reality.max_claims + earned_claims + granted_claims;
+
+        if current_claims >= allowance {
+            let mut reason = format!("No available claim. You already have {current_claims} of {allowance} claims in {}", reality.name);
+            if reality.points_per_claim > 0 {
+                reason.push_str(&format!(". Earn {} more points for another claim", (earned_claims + 1) * reality.points_per_claim - stats.points));
+            }
+
+            return Err(reason);
+        }
+
+        if current_claims > 0 && stats.slots_completed < reality.min_completed {
+            return Err(format!(
+                "You must complete {} slots before holding more than one claim in {}. You have completed {}",
+                reality.min_completed, reality.name, stats.slots_completed
+            ));
+        }
+
+        let caps: HashMap<_, _> = query!("SELECT game, max_claims FROM reality_game_caps WHERE reality = ?", reality.id)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|_| "Failed to get game limits")?
+            .into_iter()
+            .map(|record| (record.game, record.max_claims))
+            .collect();
+
+        let capped_games: Vec<_> = split_game_str(games).filter_map(|(game, _)| caps.get_key_value(game)).collect();
+
+        if !capped_games.is_empty() {
+            let claimed_games = query!(
+                "SELECT games FROM claims INNER JOIN tracked_slots ON claims.slot = tracked_slots.id INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id
+                WHERE player = ? AND reality = ? AND free = 0 AND status < 2 AND archived = 0",
+                player,
+                reality.id
+            )
+            .fetch_all(&self.db)
+            .await
+            .map_err(|_| "Failed to get current claims")?;
+
+            for (game, max_claims) in capped_games {
+                let claimed = claimed_games
+                    .iter()
+                    .filter(|record| split_game_str(&record.games).any(|(claimed_game, _)| claimed_game == game))
+                    .count() as i64;
+
+                if claimed >= *max_claims {
+                    return Err(format!(
+                        "You can only hold {max_claims} {game} {} at a time in {}",
+                        if *max_claims == 1 { "slot" } else { "slots" },
+                        reality.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -36,7 +36,7 @@ pub async fn fetch_tracker(id: &str) -> Result<String, reqwest::Error> {
 impl Bot {
     pub async fn update_scrape(&self, world: &str) {
         let (id, tracker_id) = if let Ok(response) = query!(
-            "SELECT id, tracker_id FROM tracked_worlds WHERE name = ? AND last_scrape < (strftime('%s', 'now') - 3600) LIMIT 1",
+            "SELECT id, tracker_id FROM tracked_worlds WHERE name = ? AND archived = 0 AND last_scrape < (strftime('%s', 'now') - 3600) LIMIT 1",
             world
         )
         .fetch_one(&self.db)
@@ -81,10 +81,11 @@ impl Bot {
             let status_i64 = data.status.as_i64();
             let last_activity_option = data.last_activity.to_option();
             if query!(
-                "UPDATE tracked_slots SET status = ?, checks = ?, last_activity = ? WHERE name = ? AND world = ?",
+                "UPDATE tracked_slots SET status = ?, checks = ?, last_activity = ?, goal_timestamp = CASE WHEN ? IN (2, 4) THEN COALESCE(goal_timestamp, strftime('%s', 'now')) ELSE goal_timestamp END WHERE name = ? AND world = ?",
                 status_i64,
                 data.checks,
                 last_activity_option,
+                status_i64,
                 slot,
                 id
             )
@@ -99,6 +100,8 @@ impl Bot {
         if all_goal && query!("UPDATE tracked_worlds SET done = 1 WHERE id = ?", id).execute(&self.db).await.is_err() {
             println!("Failed to mark world {world} as done");
         }
+
+        self.live_board_needed();
     }
 }
 
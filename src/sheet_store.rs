@@ -0,0 +1,366 @@
+use std::{
+    collections::HashMap,
+    fs::{read, write},
+    path::PathBuf,
+};
+
+use google_sheets4::{
+    api::{BatchUpdateValuesRequest, ClearValuesRequest, ValueRange},
+    hyper_util::{
+        client::legacy::{connect::HttpConnector, Client as SheetsClient},
+        rt::TokioExecutor,
+    },
+    yup_oauth2::{read_service_account_key, ServiceAccountAuthenticator},
+    Sheets,
+};
+use http_body_util::combinators::BoxBody;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use rustls::lock::Mutex;
+use serde_json::{from_slice, to_vec_pretty, Value};
+
+const SHEET_ID: &str = "1f0lmzxugcrut7q0Y8dSmCzZkfHw__Rwu-z6PCy3j7s4";
+
+#[derive(Clone, Copy)]
+pub enum Dimension {
+    #[allow(dead_code)]
+    Rows,
+    Columns,
+}
+
+impl Dimension {
+    fn as_str(self) -> &'static str {
+        match self {
+            Dimension::Rows => "ROWS",
+            Dimension::Columns => "COLUMNS",
+        }
+    }
+}
+
+// Ranges use A1 notation, e.g. "autodata!A1:D" or "Archivists!D2:D". Reads return one grid per range
+pub trait SheetStore {
+    async fn clear(&self, range: &str) -> Result<(), String>;
+
+    async fn update(&self, range: &str, rows: Vec<Vec<Value>>) -> Result<(), String>;
+
+    // Writes several ranges in a single request
+    async fn batch_update(&self, updates: Vec<(String, Vec<Vec<Value>>)>) -> Result<(), String>;
+
+    async fn get(&self, ranges: &[&str], dimension: Dimension) -> Result<Vec<Vec<Vec<Value>>>, String>;
+}
+
+// Picked at startup: the real spreadsheet, or a local one when SHEETS_FILE is set
+pub enum SheetBackend {
+    Google(Box<GoogleSheets>),
+    Local(LocalSheets),
+}
+
+impl SheetBackend {
+    pub async fn from_env() -> Self {
+        match std::env::var("SHEETS_FILE") {
+            Ok(path) if !path.is_empty() => SheetBackend::Local(LocalSheets::open(path)),
+            _ => SheetBackend::Google(Box::new(GoogleSheets::connect("sheets_key.json").await)),
+        }
+    }
+}
+
+impl SheetStore for SheetBackend {
+    async fn clear(&self, range: &str) -> Result<(), String> {
+        match self {
+            SheetBackend::Google(sheets) => sheets.clear(range).await,
+            SheetBackend::Local(sheets) => sheets.clear(range).await,
+        }
+    }
+
+    async fn update(&self, range: &str, rows: Vec<Vec<Value>>) -> Result<(), String> {
+        match self {
+            SheetBackend::Google(sheets) => sheets.update(range, rows).await,
+            SheetBackend::Local(sheets) => sheets.update(range, rows).await,
+        }
+    }
+
+    async fn batch_update(&self, updates: Vec<(String, Vec<Vec<Value>>)>) -> Result<(), String> {
+        match self {
+            SheetBackend::Google(sheets) => sheets.batch_update(updates).await,
+            SheetBackend::Local(sheets) => sheets.batch_update(updates).await,
+        }
+    }
+
+    async fn get(&self, ranges: &[&str], dimension: Dimension) -> Result<Vec<Vec<Vec<Value>>>, String> {
+        match self {
+            SheetBackend::Google(sheets) => sheets.get(ranges, dimension).await,
+            SheetBackend::Local(sheets) => sheets.get(ranges, dimension).await,
+        }
+    }
+}
+
+pub struct GoogleSheets {
+    sheets: Sheets<HttpsConnector<HttpConnector>>,
+}
+
+impl GoogleSheets {
+    pub async fn connect(key_file: &str) -> Self {
+        let client: SheetsClient<_, BoxBody<google_sheets4::hyper::body::Bytes, google_sheets4::hyper::Error>> = SheetsClient::builder(TokioExecutor::new()).build(
+            HttpsConnectorBuilder::new()
+                .with_native_roots()
+                .expect("Failed to set tls config")
+                .https_only()
+                .enable_http1()
+                .enable_http2()
+                .build(),
+        );
+        let secret = read_service_account_key(key_file).await.expect("Failed to find sheets api key file");
+        let auth = ServiceAccountAuthenticator::with_client(
+            secret,
+            SheetsClient::builder(TokioExecutor::new()).build(
+                HttpsConnectorBuilder::new()
+                    .with_native_roots()
+                    .expect("Failed to set tls config")
+                    .https_only()
+                    .enable_http1()
+                    .enable_http2()
+                    .build(),
+            ),
+        )
+        .build()
+        .await
+        .expect("Failed to create an authenticator");
+
+        GoogleSheets { sheets: Sheets::new(client, auth) }
+    }
+}
+
+impl SheetStore for GoogleSheets {
+    async fn clear(&self, range: &str) -> Result<(), String> {
+        self.sheets
+            .spreadsheets()
+            .values_clear(ClearValuesRequest::default(), SHEET_ID, range)
+            .doit()
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    async fn update(&self, range: &str, rows: Vec<Vec<Value>>) -> Result<(), String> {
+        self.sheets
+            .spreadsheets()
+            .values_update(
+                ValueRange {
+                    major_dimension: Some(String::from("ROWS")),
+                    range: Some(String::from(range)),
+                    values: Some(rows),
+                },
+                SHEET_ID,
+                range,
+            )
+            .value_input_option("RAW")
+            .doit()
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    async fn batch_update(&self, updates: Vec<(String, Vec<Vec<Value>>)>) -> Result<(), String> {
+        let data = updates
+            .into_iter()
+            .map(|(range, rows)| ValueRange {
+                major_dimension: Some(String::from("ROWS")),
+                range: Some(range),
+                values: Some(rows),
+            })
+            .collect();
+
+        self.sheets
+            .spreadsheets()
+            .values_batch_update(
+                BatchUpdateValuesRequest {
+                    data: Some(data),
+                    value_input_option: Some(String::from("RAW")),
+                    ..Default::default()
+                },
+                SHEET_ID,
+            )
+            .doit()
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    async fn get(&self, ranges: &[&str], dimension: Dimension) -> Result<Vec<Vec<Vec<Value>>>, String> {
+        let request = ranges.iter().fold(self.sheets.spreadsheets().values_batch_get(SHEET_ID), |request, range| request.add_ranges(range));
+        let (_, response) = request.major_dimension(dimension.as_str()).doit().await.map_err(|err| err.to_string())?;
+
+        Ok(response.value_ranges.unwrap_or_default().into_iter().map(|range| range.values.unwrap_or_default()).collect())
+    }
+}
+
+// A spreadsheet kept in memory, optionally saved to a json file after every write so it can be inspected
+pub struct LocalSheets {
+    path: Option<PathBuf>,
+    sheets: Mutex<HashMap<String, Vec<Vec<Value>>>>,
+}
+
+impl LocalSheets {
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        LocalSheets {
+            path: None,
+            sheets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let sheets = read(&path).ok().and_then(|bytes| from_slice(&bytes).ok()).unwrap_or_default();
+
+        LocalSheets {
+            path: Some(path),
+            sheets: Mutex::new(sheets),
+        }
+    }
+
+    fn write(&self, range: &str, apply: impl FnOnce(&mut Vec<Vec<Value>>, &GridRange)) -> Result<(), String> {
+        let range = GridRange::parse(range).ok_or_else(|| format!("Invalid range {range}"))?;
+        let mut sheets = self.sheets.lock().ok_or("Failed to aquire local sheets lock")?;
+        apply(sheets.entry(range.sheet.clone()).or_default(), &range);
+
+        if let Some(path) = &self.path {
+            let bytes = to_vec_pretty(&*sheets).map_err(|err| err.to_string())?;
+            write(path, bytes).map_err(|err| err.to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SheetStore for LocalSheets {
+    async fn clear(&self, range: &str) -> Result<(), String> {
+        self.write(range, |grid, range| {
+            let end_row = range.end_row.map_or(grid.len(), |end| (end + 1).min(grid.len()));
+
+            for row in grid.iter_mut().take(end_row).skip(range.start_row) {
+                for cell in row.iter_mut().take(range.end_column + 1).skip(range.start_column) {
+                    *cell = Value::Null;
+                }
+            }
+        })
+    }
+
+    async fn update(&self, range: &str, rows: Vec<Vec<Value>>) -> Result<(), String> {
+        self.write(range, |grid, range| {
+            for (offset, values) in rows.into_iter().enumerate() {
+                let row = range.start_row + offset;
+                if grid.len() <= row {
+                    grid.resize(row + 1, Vec::new());
+                }
+
+                for (column, value) in (range.start_column..).zip(values) {
+                    if grid[row].len() <= column {
+                        grid[row].resize(column + 1, Value::Null);
+                    }
+                    grid[row][column] = value;
+                }
+            }
+        })
+    }
+
+    async fn batch_update(&self, updates: Vec<(String, Vec<Vec<Value>>)>) -> Result<(), String> {
+        for (range, rows) in updates {
+            self.update(&range, rows).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, ranges: &[&str], dimension: Dimension) -> Result<Vec<Vec<Vec<Value>>>, String> {
+        let sheets = self.sheets.lock().ok_or("Failed to aquire local sheets lock")?;
+
+        ranges
+            .iter()
+            .map(|range| {
+                let range = GridRange::parse(range).ok_or_else(|| format!("Invalid range {range}"))?;
+                let grid = sheets.get(&range.sheet).map(Vec::as_slice).unwrap_or_default();
+                let end_row = range.end_row.map_or(grid.len(), |end| (end + 1).min(grid.len()));
+                let cell = |row: usize, column: usize| grid.get(row).and_then(|row| row.get(column)).cloned().unwrap_or(Value::Null);
+
+                let lines: Vec<Vec<Value>> = match dimension {
+                    Dimension::Rows => (range.start_row..end_row)
+                        .map(|row| (range.start_column..=range.end_column).map(|column| cell(row, column)).collect())
+                        .collect(),
+                    Dimension::Columns => (range.start_column..=range.end_column)
+                        .map(|column| (range.start_row..end_row).map(|row| cell(row, column)).collect())
+                        .collect(),
+                };
+
+                Ok(trim(
+                    lines.into_iter().map(|line| trim(line, |cell| cell.is_null() || cell.as_str() == Some(""))).collect(),
+                    Vec::is_empty,
+                ))
+            })
+            .collect()
+    }
+}
+
+// Drops trailing empty entries, matching what the sheets api leaves out of its responses
+fn trim<T>(mut values: Vec<T>, is_empty: impl Fn(&T) -> bool) -> Vec<T> {
+    while values.last().is_some_and(&is_empty) {
+        values.pop();
+    }
+    values
+}
+
+// Zero based, with inclusive ends. Ranges like A2:A leave the last row open
+struct GridRange {
+    sheet: String,
+    start_column: usize,
+    start_row: usize,
+    end_column: usize,
+    end_row: Option<usize>,
+}
+
+impl GridRange {
+    fn parse(range: &str) -> Option<Self> {
+        let (sheet, cells) = range.split_once('!')?;
+        let (start, end) = cells.split_once(':').unwrap_or((cells, cells));
+        let (start_column, start_row) = parse_cell(start)?;
+        let (end_column, end_row) = parse_cell(end)?;
+
+        Some(GridRange {
+            sheet: sheet.trim_matches('\'').to_owned(),
+            start_column,
+            start_row: start_row.unwrap_or(0),
+            end_column,
+            end_row,
+        })
+    }
+}
+
+// The sheet and zero based top left cell of a range
+pub fn range_start(range: &str) -> Option<(String, usize, usize)> {
+    GridRange::parse(range).map(|range| (range.sheet, range.start_column, range.start_row))
+}
+
+pub fn column_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut index = index + 1;
+
+    while index > 0 {
+        name.push(b'A' + ((index - 1) % 26) as u8);
+        index = (index - 1) / 26;
+    }
+
+    name.iter().rev().map(|&letter| char::from(letter)).collect()
+}
+
+fn parse_cell(cell: &str) -> Option<(usize, Option<usize>)> {
+    let split = cell.find(|c: char| c.is_ascii_digit()).unwrap_or(cell.len());
+    let (letters, digits) = cell.split_at(split);
+
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+
+    let column = letters.bytes().fold(0, |column, letter| column * 26 + usize::from(letter - b'A' + 1)) - 1;
+    let row = if digits.is_empty() { None } else { Some(digits.parse::<usize>().ok()?.checked_sub(1)?) };
+
+    Some((column, row))
+}
@@ -1,24 +1,203 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    fs::read_to_string,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use google_sheets4::api::{ClearValuesRequest, ValueRange};
-use http_body_util::BodyExt;
 use serde::Deserialize;
-use serde_json::{from_slice, json};
-use sqlx::query;
+use serde_json::{from_str, json, Value};
+use sqlx::{query, query_as};
 
-use crate::{scrape::Status, Bot};
+use crate::{
+    scrape::Status,
+    sheet_store::{column_name, range_start, Dimension, SheetStore},
+    Bot,
+};
 
-const SHEET_ID: &str = "1f0lmzxugcrut7q0Y8dSmCzZkfHw__Rwu-z6PCy3j7s4";
+// The rows of every table as they were last written
+pub type SheetSnapshot = Vec<Vec<Vec<Value>>>;
 
-#[derive(Debug, Deserialize)]
-struct UnspentPointsResponse {
-    #[serde(rename = "valueRanges")]
-    value_ranges: [UnspentPointsResponseValue; 2],
+// What each row of a table is made from
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rows {
+    Slots,
+    Players,
 }
 
-#[derive(Debug, Deserialize)]
-struct UnspentPointsResponseValue {
-    values: [Vec<String>; 1],
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    World,
+    Reality,
+    Slot,
+    Games,
+    Status,
+    Checks,
+    Player,
+    Points,
+    LastActivity,
+    LatestUpdate,
+}
+
+struct SlotRecord {
+    world: String,
+    reality: Option<String>,
+    slot: String,
+    games: String,
+    status: i64,
+    checks: i64,
+    checks_total: i64,
+    free: i64,
+    points: i64,
+    last_activity: Option<i64>,
+    player: Option<String>,
+    latest_update: Option<String>,
+}
+
+struct PlayerRecord {
+    name: String,
+    points: i64,
+}
+
+impl Column {
+    fn slot_value(self, record: &SlotRecord) -> Value {
+        match self {
+            Column::World => json!(record.world),
+            Column::Reality => json!(record.reality.as_deref().unwrap_or_default()),
+            Column::Slot => json!(record.slot),
+            Column::Games => json!(record.games),
+            Column::Status => json!(match (&record.player, Status::from_i64(record.status)) {
+                (None, _) if record.free > 0 => "Unclaimed [Free claim]",
+                (None, _) => "Unclaimed",
+                (Some(_), Some(status)) => status.as_str(),
+                (Some(_), None) => "Unknown",
+            }),
+            Column::Checks => json!(format!("{}/{}", record.checks, record.checks_total)),
+            Column::Player => json!(record.player.as_deref().unwrap_or_default()),
+            Column::Points => json!(record.points),
+            Column::LastActivity => record.last_activity.map_or(json!(""), |minutes| json!(minutes)),
+            Column::LatestUpdate => json!(record.latest_update.as_deref().unwrap_or_default()),
+        }
+    }
+
+    fn player_value(self, record: &PlayerRecord) -> Option<Value> {
+        match self {
+            Column::Player => Some(json!(record.name)),
+            Column::Points => Some(json!(record.points)),
+            _ => None,
+        }
+    }
+}
+
+// A table as written in the layout file, e.g. { "range": "autodata!A1", "rows": "slots", "columns": ["world", "slot", "status", "player"] }
+#[derive(Deserialize)]
+struct TableConfig {
+    range: String,
+    rows: Rows,
+    columns: Vec<Column>,
+}
+
+// A block of columns on the sheet that is filled with one row per record
+pub struct Table {
+    sheet: String,
+    column: usize,
+    row: usize,
+    rows: Rows,
+    columns: Vec<Column>,
+}
+
+// Where the sheet push writes to. Hosts can replace the default with a json file named by SHEET_LAYOUT
+pub fn load_layout() -> Vec<Table> {
+    match std::env::var("SHEET_LAYOUT") {
+        Ok(path) if !path.is_empty() => {
+            let layout = read_to_string(&path).expect("Failed to read sheet layout file");
+            parse_layout(&layout).unwrap_or_else(|err| panic!("Invalid sheet layout in {path}: {err}"))
+        }
+        _ => default_layout(),
+    }
+}
+
+pub fn default_layout() -> Vec<Table> {
+    parse_layout(
+        r#"[
+            { "range": "autodata!A1", "rows": "slots", "columns": ["world", "slot", "status", "player"] },
+            { "range": "autodata!F1", "rows": "players", "columns": ["player", "points"] }
+        ]"#,
+    )
+    .expect("The default sheet layout should be valid")
+}
+
+pub fn parse_layout(layout: &str) -> Result<Vec<Table>, String> {
+    let tables: Vec<TableConfig> = from_str(layout).map_err(|err| err.to_string())?;
+
+    tables
+        .into_iter()
+        .map(|table| {
+            let (sheet, column, row) = range_start(&table.range).ok_or_else(|| format!("Invalid range {}", table.range))?;
+
+            if table.columns.is_empty() {
+                return Err(format!("The table at {} has no columns", table.range));
+            }
+
+            if table.rows == Rows::Players && table.columns.iter().any(|column| !matches!(column, Column::Player | Column::Points)) {
+                return Err(format!("The player table at {} can only have player and points columns", table.range));
+            }
+
+            Ok(Table {
+                sheet,
+                column,
+                row,
+                rows: table.rows,
+                columns: table.columns,
+            })
+        })
+        .collect()
+}
+
+impl Table {
+    // Covers `len` rows starting `start` rows into the table
+    fn range(&self, start: usize, len: usize) -> String {
+        let first = self.row + start + 1;
+        format!(
+            "{}!{}{first}:{}{}",
+            self.sheet,
+            column_name(self.column),
+            column_name(self.column + self.columns.len() - 1),
+            first + len - 1
+        )
+    }
+
+    fn open_range(&self) -> String {
+        format!("{}!{}{}:{}", self.sheet, column_name(self.column), self.row + 1, column_name(self.column + self.columns.len() - 1))
+    }
+
+    // Only rows that changed are sent, unless rows were added or removed, in which case the whole table is rewritten and any rows past the new end are blanked
+    fn diff(&self, previous: &[Vec<Value>], rows: &[Vec<Value>]) -> Vec<(String, Vec<Vec<Value>>)> {
+        if previous.len() != rows.len() {
+            let mut rows = rows.to_vec();
+            rows.resize(previous.len().max(rows.len()), vec![json!(""); self.columns.len()]);
+            return vec![(self.range(0, rows.len()), rows)];
+        }
+
+        let mut updates = Vec::new();
+        let mut index = 0;
+
+        while index < rows.len() {
+            if previous[index] == rows[index] {
+                index += 1;
+                continue;
+            }
+
+            let start = index;
+            while index < rows.len() && previous[index] != rows[index] {
+                index += 1;
+            }
+
+            updates.push((self.range(start, index - start), rows[start..index].to_vec()));
+        }
+
+        updates
+    }
 }
 
 impl Bot {
@@ -30,6 +209,7 @@ impl Bot {
             return;
         }
 
+        self.live_board_needed();
         self.push_to_sheet().await;
     }
 
@@ -53,106 +233,101 @@ impl Bot {
             return;
         }
 
-        let _ = self.sheets.spreadsheets().values_clear(ClearValuesRequest::default(), SHEET_ID, "autodata!A1:G").doit().await;
-
-        let Ok(data) = query!("SELECT world, slot, status, free, player FROM sheets_push").fetch_all(&self.db).await else {
+        let Ok(slots) = query_as!(
+            SlotRecord,
+            r#"SELECT world AS "world!", reality, slot AS "slot!", games AS "games!", status AS "status!", checks AS "checks!", checks_total AS "checks_total!",
+            free AS "free!", points AS "points!", last_activity, player, latest_update AS "latest_update?: String" FROM sheets_push"#
+        )
+        .fetch_all(&self.db)
+        .await
+        else {
             return;
         };
 
-        let _ = self
-            .sheets
-            .spreadsheets()
-            .values_update(
-                ValueRange {
-                    major_dimension: Some(String::from("ROWS")),
-                    range: Some(String::from("autodata!A1:D")),
-                    values: Some(
-                        data.into_iter()
-                            .filter_map(|record| {
-                                Status::from_i64(record.status).map(|status| {
-                                    vec![
-                                        json!(record.world),
-                                        json!(record.slot),
-                                        json!(if record.player.is_none() {
-                                            if record.free > 0 {
-                                                "Unclaimed [Free claim]"
-                                            } else {
-                                                "Unclaimed"
-                                            }
-                                        } else {
-                                            status.as_str()
-                                        }),
-                                        json!(record.player),
-                                    ]
-                                })
-                            })
-                            .collect(),
-                    ),
-                },
-                SHEET_ID,
-                "autodata!A1:D",
-            )
-            .value_input_option("RAW")
-            .doit()
-            .await;
-
-        let Ok(data) = query!("SELECT name, points FROM players ORDER BY id").fetch_all(&self.db).await else {
+        let Ok(players) = query_as!(PlayerRecord, "SELECT name, points FROM players ORDER BY id").fetch_all(&self.db).await else {
             return;
         };
 
-        let _ = self
-            .sheets
-            .spreadsheets()
-            .values_update(
-                ValueRange {
-                    major_dimension: Some(String::from("ROWS")),
-                    range: Some(String::from("autodata!F1:G")),
-                    values: Some(data.into_iter().map(|record| vec![json!(record.name), json!(record.points)]).collect()),
-                },
-                SHEET_ID,
-                "autodata!F1:G",
-            )
-            .value_input_option("RAW")
-            .doit()
-            .await;
-    }
+        let tables: SheetSnapshot = self
+            .sheet_layout
+            .iter()
+            .map(|table| match table.rows {
+                Rows::Slots => slots.iter().map(|record| table.columns.iter().map(|column| column.slot_value(record)).collect()).collect(),
+                Rows::Players => players.iter().map(|record| table.columns.iter().filter_map(|column| column.player_value(record)).collect()).collect(),
+            })
+            .collect();
 
-    pub async fn update_unspent_points(&self) {
-        let response = match self
-            .sheets
-            .spreadsheets()
-            .values_batch_get(SHEET_ID)
-            .add_ranges("Archivists!A2:A")
-            .add_ranges("Archivists!D2:D")
-            .major_dimension("COLUMNS")
-            .doit()
-            .await
-        {
-            Ok((response, _)) => response.into_body(),
-            Err(err) => {
-                dbg!(err);
-                return;
+        let previous = self.sheet_snapshot.lock().and_then(|mut snapshot| snapshot.take());
+
+        let result = match &previous {
+            Some(previous) => {
+                let updates: Vec<_> = self
+                    .sheet_layout
+                    .iter()
+                    .zip(previous)
+                    .zip(&tables)
+                    .flat_map(|((table, previous), rows)| table.diff(previous, rows))
+                    .collect();
+
+                if updates.is_empty() {
+                    Ok(())
+                } else {
+                    self.sheets.batch_update(updates).await
+                }
             }
+            None => self.rewrite_sheet(&tables).await,
         };
 
-        let bytes = match response.collect().await {
-            Ok(bytes) => bytes.to_bytes(),
+        match result {
+            Ok(()) => {
+                if let Some(mut snapshot) = self.sheet_snapshot.lock() {
+                    *snapshot = Some(tables);
+                }
+            }
             Err(err) => {
-                dbg!(err);
-                return;
+                println!("Failed to push to the sheet: {err}");
+
+                // Without a snapshot the next push rewrites everything
+                if let Some(mut pending_guard) = self.pending_push.lock() {
+                    *pending_guard = true;
+                }
             }
-        };
+        }
+    }
 
-        let data = match from_slice::<UnspentPointsResponse>(&bytes) {
+    // Nothing is known about what is on the sheet, so clear it before writing
+    async fn rewrite_sheet(&self, tables: &[Vec<Vec<Value>>]) -> Result<(), String> {
+        for table in &self.sheet_layout {
+            self.sheets.clear(&table.open_range()).await?;
+        }
+
+        let updates = self
+            .sheet_layout
+            .iter()
+            .zip(tables)
+            .filter(|(_, rows)| !rows.is_empty())
+            .map(|(table, rows)| (table.range(0, rows.len()), rows.clone()))
+            .collect();
+        self.sheets.batch_update(updates).await
+    }
+
+    pub async fn update_unspent_points(&self) {
+        let data = match self.sheets.get(&["Archivists!A2:A", "Archivists!D2:D"], Dimension::Columns).await {
             Ok(data) => data,
             Err(err) => {
-                dbg!(err);
+                println!("Failed to read unspent points: {err}");
                 return;
             }
         };
 
-        for (name, unspent) in data.value_ranges[0].values[0].iter().zip(data.value_ranges[1].values[0].iter()) {
-            if let Ok(unspent) = unspent.parse::<i64>() {
+        let column = |index: usize| data.get(index).and_then(|range| range.first()).map(Vec::as_slice).unwrap_or_default();
+
+        for (name, unspent) in column(0).iter().zip(column(1)) {
+            let Some(name) = name.as_str().filter(|name| !name.is_empty()) else {
+                continue;
+            };
+
+            if let Some(unspent) = parse_points(unspent) {
                 if let Err(err) = query!("UPDATE players SET unspent_points = ? WHERE name = lower(?)", unspent, name).execute(&self.db).await {
                     println!("Failed to set unspent points for {name}: {err:?}");
                 }
@@ -162,3 +337,8 @@ impl Bot {
         }
     }
 }
+
+// The sheets api hands back formatted strings, but a local sheet keeps whatever was written
+fn parse_points(value: &Value) -> Option<i64> {
+    value.as_i64().or_else(|| value.as_str()?.trim().parse().ok())
+}
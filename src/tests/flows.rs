@@ -0,0 +1,210 @@
+use sqlx::{query, raw_sql};
+
+use super::{command, FakeDiscord, Sent, TestBot, ADMIN, HELPER, PLAYER};
+use crate::{
+    channels::STATUS_CHANNEL,
+    commands::{claim::ClaimCommand, done::DoneCommand, finish_world::FinishWorldCommand, status::StatusCommand},
+};
+
+async fn seeded_bot() -> TestBot {
+    let bot = TestBot::new().await;
+    bot.execute(
+        "INSERT INTO tracked_worlds (id, tracker_id, name) VALUES (1, 'tracker', 'Alpha');
+        INSERT INTO tracked_slots (id, world, name, games, status, checks, checks_total, points) VALUES (1, 1, 'Ness', 'EarthBound', 0, 0, 10, 3), (2, 1, 'Lucas', 'Mother 3', 0, 0, 10, 2);",
+    )
+    .await;
+    bot
+}
+
+fn contents(sent: &[Sent]) -> Vec<&str> {
+    sent.iter().filter_map(Sent::content).collect()
+}
+
+#[tokio::test]
+async fn claim_status_done_finish_world() {
+    let bot = seeded_bot().await;
+    let discord = FakeDiscord::default();
+
+    ClaimCommand::run(&bot, &discord, &command("claim", PLAYER, &[("world", "Alpha"), ("slot", "Ness")])).await;
+    assert_eq!(contents(&discord.take()), ["Successfully claimed Ness in Alpha"]);
+
+    StatusCommand::run(&bot, &discord, &command("status", PLAYER, &[("world", "Alpha"), ("slot", "Ness"), ("description", "Beat Giygas")])).await;
+    let sent = discord.take();
+    assert!(matches!(&sent[1], Sent::Message(channel, _) if channel.get() == STATUS_CHANNEL));
+    assert_eq!(contents(&sent), ["[user3] [Alpha] [Ness] Beat Giygas", "Successfully updated status of Ness in Alpha"]);
+
+    DoneCommand::run(&bot, &discord, &command("done", HELPER, &[("world", "Alpha"), ("slot", "Ness")])).await;
+    assert_eq!(contents(&discord.take()), ["Successfully marked slot as done"]);
+
+    FinishWorldCommand::run(&bot, &discord, &command("finish-world", ADMIN, &[("world", "Alpha")])).await;
+    let sent = discord.take();
+    let Some(Sent::Message(channel, message)) = sent.iter().find(|sent| matches!(sent, Sent::Message(..))) else {
+        panic!("The results should be posted to the status channel");
+    };
+    assert_eq!(channel.get(), STATUS_CHANNEL);
+    assert_eq!(message["embeds"][0]["title"], "Alpha completed!");
+    assert_eq!(message["embeds"][0]["description"], "**Ness** [<@3>]\n**Lucas** [*Unclaimed*]");
+    assert_eq!(contents(&sent), ["Successfully finished world Alpha"]);
+
+    let player = query!("SELECT id, points, season_points, slots_completed, goals_reached FROM players WHERE snowflake = 3")
+        .fetch_one(&bot.db)
+        .await
+        .unwrap();
+    assert_eq!((player.points, player.season_points, player.slots_completed, player.goals_reached), (3, 3, 1, 1));
+
+    let slot = query!("SELECT status, awarded_to FROM tracked_slots WHERE id = 1").fetch_one(&bot.db).await.unwrap();
+    assert_eq!((slot.status, slot.awarded_to), (4, Some(player.id)));
+
+    let world = query!("SELECT archived FROM tracked_worlds WHERE id = 1").fetch_one(&bot.db).await.unwrap();
+    assert_eq!(world.archived, 1);
+}
+
+#[tokio::test]
+async fn claimed_slots_cannot_be_claimed_again() {
+    let bot = seeded_bot().await;
+    let discord = FakeDiscord::default();
+
+    ClaimCommand::run(&bot, &discord, &command("claim", PLAYER, &[("world", "Alpha"), ("slot", "Ness")])).await;
+    ClaimCommand::run(&bot, &discord, &command("claim", HELPER, &[("world", "Alpha"), ("slot", "Ness")])).await;
+    assert_eq!(contents(&discord.take()), ["Successfully claimed Ness in Alpha", "Slot is already claimed"]);
+}
+
+#[tokio::test]
+async fn finishing_a_world_requires_an_admin() {
+    let bot = seeded_bot().await;
+    let discord = FakeDiscord::default();
+
+    FinishWorldCommand::run(&bot, &discord, &command("finish-world", HELPER, &[("world", "Alpha")])).await;
+    assert_eq!(contents(&discord.take()), ["You do not have permission to use this command"]);
+
+    let world = query!("SELECT archived FROM tracked_worlds WHERE id = 1").fetch_one(&bot.db).await.unwrap();
+    assert_eq!(world.archived, 0);
+}
+
+#[tokio::test]
+async fn missing_options_are_reported() {
+    let bot = seeded_bot().await;
+    let discord = FakeDiscord::default();
+
+    ClaimCommand::run(&bot, &discord, &command("claim", PLAYER, &[("world", "Alpha")])).await;
+    assert_eq!(contents(&discord.take()), ["The `slot` option is required"]);
+
+    StatusCommand::run(&bot, &discord, &command("status", PLAYER, &[("world", "Alpha"), ("slot", "Ness"), ("description", " ")])).await;
+    assert_eq!(contents(&discord.take()), ["The `description` option has an invalid value"]);
+}
+
+#[tokio::test]
+async fn only_admins_can_undo_other_peoples_actions() {
+    let bot = seeded_bot().await;
+    bot.execute(r#"INSERT INTO undo_log (id, actor, command, description, payload) VALUES (1, 1, 'mark-free', 'Marked Ness as free', '{"kind":"MarkFree","slot":1,"free":0}');"#)
+        .await;
+
+    assert_eq!(bot.undo(HELPER.get() as i64, false, Some(1)).await, Err(String::from("There is nothing to undo")));
+    assert_eq!(bot.undo(ADMIN.get() as i64, true, Some(1)).await, Ok(String::from("Marked Ness as free")));
+}
+
+#[tokio::test]
+async fn only_finished_slots_are_counted_as_completed() {
+    let bot = seeded_bot().await;
+    let discord = FakeDiscord::default();
+
+    ClaimCommand::run(&bot, &discord, &command("claim", PLAYER, &[("world", "Alpha"), ("slot", "Ness")])).await;
+    ClaimCommand::run(&bot, &discord, &command("claim", PLAYER, &[("world", "Alpha"), ("slot", "Lucas")])).await;
+    bot.execute("UPDATE tracked_slots SET status = 3 WHERE id = 1;").await;
+    FinishWorldCommand::run(&bot, &discord, &command("finish-world", ADMIN, &[("world", "Alpha")])).await;
+
+    // All checks without a goal counts as a completed slot but not as a goal
+    let player = query!("SELECT points, slots_completed, goals_reached FROM players WHERE snowflake = 3")
+        .fetch_one(&bot.db)
+        .await
+        .unwrap();
+    assert_eq!((player.points, player.slots_completed, player.goals_reached), (5, 1, 0));
+
+    assert!(bot.undo(ADMIN.get() as i64, true, None).await.is_ok());
+    let player = query!("SELECT points, slots_completed, goals_reached FROM players WHERE snowflake = 3")
+        .fetch_one(&bot.db)
+        .await
+        .unwrap();
+    assert_eq!((player.points, player.slots_completed, player.goals_reached), (0, 0, 0));
+}
+
+#[tokio::test]
+async fn finished_worlds_cannot_be_undone_after_a_new_season() {
+    let bot = seeded_bot().await;
+    let discord = FakeDiscord::default();
+
+    ClaimCommand::run(&bot, &discord, &command("claim", PLAYER, &[("world", "Alpha"), ("slot", "Ness")])).await;
+    FinishWorldCommand::run(&bot, &discord, &command("finish-world", ADMIN, &[("world", "Alpha")])).await;
+    bot.execute("INSERT INTO seasons (name) VALUES ('Spring'); UPDATE players SET season_points = 0;").await;
+
+    assert_eq!(
+        bot.undo(ADMIN.get() as i64, true, None).await,
+        Err(String::from("A new season has started since the world was finished"))
+    );
+    let world = query!("SELECT archived FROM tracked_worlds WHERE id = 1").fetch_one(&bot.db).await.unwrap();
+    assert_eq!(world.archived, 1);
+}
+
+#[tokio::test]
+async fn finished_worlds_free_their_name_and_tracker() {
+    let bot = seeded_bot().await;
+    let discord = FakeDiscord::default();
+
+    FinishWorldCommand::run(&bot, &discord, &command("finish-world", ADMIN, &[("world", "Alpha")])).await;
+    bot.execute("INSERT INTO tracked_worlds (id, tracker_id, name) VALUES (2, 'tracker', 'Alpha');").await;
+
+    let duplicate = query!("INSERT INTO tracked_worlds (tracker_id, name) VALUES ('tracker', 'Alpha')").execute(&bot.db).await;
+    assert!(duplicate.is_err(), "Live worlds should still have unique names");
+}
+
+#[tokio::test]
+async fn undoing_an_unclaim_clears_the_cooldown() {
+    let bot = seeded_bot().await;
+    bot.execute(
+        r#"INSERT INTO players (id, snowflake, name) VALUES (1, 3, 'user3');
+        INSERT INTO released_claims (id, player) VALUES (1, 1);
+        INSERT INTO undo_log (id, actor, command, description, payload) VALUES (1, 2, 'unclaim', 'Unclaimed Ness in Alpha', '{"kind":"Unclaim","claims":[{"slot":1,"player":1,"public":null,"timestamp":0}],"released":[1]}');"#,
+    )
+    .await;
+
+    assert_eq!(bot.undo(HELPER.get() as i64, false, None).await, Ok(String::from("Unclaimed Ness in Alpha")));
+
+    let released = query!("SELECT COUNT(*) AS count FROM released_claims").fetch_one(&bot.db).await.unwrap();
+    let claims = query!("SELECT COUNT(*) AS count FROM claims WHERE slot = 1 AND player = 1").fetch_one(&bot.db).await.unwrap();
+    assert_eq!((released.count, claims.count), (0, 1));
+}
+
+#[tokio::test]
+async fn live_world_names_migration_keeps_existing_worlds() {
+    let bot = TestBot::seeded_before(
+        "20261020010000",
+        "INSERT INTO players (id, snowflake, name) VALUES (1, 3, 'ness');
+        INSERT INTO tracked_worlds (id, tracker_id, name) VALUES (1, 'tracker', 'Alpha');
+        INSERT INTO tracked_slots (id, world, name, games, status, checks, checks_total) VALUES (1, 1, 'Ness', 'EarthBound', 0, 0, 10);
+        INSERT INTO claims (slot, player) VALUES (1, 1);",
+    )
+    .await;
+
+    let slots = query!("SELECT tracked_slots.name FROM tracked_slots INNER JOIN tracked_worlds ON tracked_slots.world = tracked_worlds.id")
+        .fetch_all(&bot.db)
+        .await
+        .unwrap();
+    assert_eq!(slots.iter().map(|slot| slot.name.as_str()).collect::<Vec<_>>(), ["Ness"]);
+    assert!(raw_sql("PRAGMA foreign_key_check").fetch_all(&bot.db).await.unwrap().is_empty());
+
+    bot.execute("UPDATE tracked_worlds SET archived = 1 WHERE id = 1; INSERT INTO tracked_worlds (tracker_id, name) VALUES ('tracker', 'Alpha');")
+        .await;
+}
+
+#[tokio::test]
+async fn saved_filters_belong_to_their_command_and_expire() {
+    let bot = TestBot::new().await;
+
+    let id = bot.save_filter("search", &["EarthBound"]).await.unwrap();
+    assert_eq!(bot.saved_filter::<Vec<String>>("search", id).await, Some(vec![String::from("EarthBound")]));
+    assert_eq!(bot.saved_filter::<Vec<String>>("audit", id).await, None);
+
+    bot.execute("UPDATE saved_filters SET timestamp = timestamp - 2 * 24 * 60 * 60;").await;
+    bot.save_filter("audit", &["Alpha"]).await.unwrap();
+    assert_eq!(bot.saved_filter::<Vec<String>>("search", id).await, None);
+}
@@ -0,0 +1,200 @@
+mod flows;
+mod sheets;
+
+use std::{
+    fs::{read_dir, read_to_string, remove_file},
+    ops::Deref,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+};
+
+use rustls::lock::Mutex;
+use serde_json::{from_value, json, to_value, Value};
+use serenity::{
+    all::{ChannelId, CommandInteraction, CreateInteractionResponse, CreateMessage, EditInteractionResponse, InteractionId, UserId},
+    Result,
+};
+use sqlx::{
+    raw_sql,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
+
+use crate::{
+    discord::Discord,
+    paginate::PageCache,
+    sheet_store::{LocalSheets, SheetBackend},
+    sheets::default_layout,
+    Bot,
+};
+
+const ADMIN: UserId = UserId::new(1);
+const HELPER: UserId = UserId::new(2);
+const PLAYER: UserId = UserId::new(3);
+
+static DATABASES: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+enum Sent {
+    Response(Value),
+    Edit(Value),
+    Message(ChannelId, Value),
+    DirectMessage(Value),
+}
+
+impl Sent {
+    fn content(&self) -> Option<&str> {
+        match self {
+            Sent::Response(response) => response["data"]["content"].as_str(),
+            Sent::Edit(message) | Sent::Message(_, message) | Sent::DirectMessage(message) => message["content"].as_str(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct FakeDiscord {
+    sent: StdMutex<Vec<Sent>>,
+}
+
+impl FakeDiscord {
+    fn take(&self) -> Vec<Sent> {
+        self.sent.lock().map(|mut sent| sent.drain(..).collect()).unwrap_or_default()
+    }
+
+    fn push(&self, sent: Sent) {
+        if let Ok(mut guard) = self.sent.lock() {
+            guard.push(sent);
+        }
+    }
+}
+
+impl Discord for FakeDiscord {
+    async fn create_response(&self, _id: InteractionId, _token: &str, response: CreateInteractionResponse) -> Result<()> {
+        self.push(Sent::Response(to_value(response)?));
+        Ok(())
+    }
+
+    async fn edit_response(&self, _token: &str, response: EditInteractionResponse) -> Result<()> {
+        self.push(Sent::Edit(to_value(response)?));
+        Ok(())
+    }
+
+    async fn send_message(&self, channel: ChannelId, message: CreateMessage) -> Result<()> {
+        self.push(Sent::Message(channel, to_value(message)?));
+        Ok(())
+    }
+
+    async fn direct_message(&self, _user: UserId, message: CreateMessage) -> Result<()> {
+        self.push(Sent::DirectMessage(to_value(message)?));
+        Ok(())
+    }
+}
+
+// A bot backed by a throwaway database with every migration applied
+struct TestBot {
+    bot: Bot,
+    path: PathBuf,
+}
+
+impl TestBot {
+    async fn new() -> Self {
+        Self::seeded_before("", "").await
+    }
+
+    // Runs `sql` just before `migration` is applied, for checking that migrations keep existing rows
+    async fn seeded_before(migration: &str, sql: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("discord_bot-test-{}-{}.sqlite", process::id(), DATABASES.fetch_add(1, Ordering::Relaxed)));
+
+        let db = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(SqliteConnectOptions::new().filename(&path).create_if_missing(true))
+            .await
+            .expect("Couldn't create test database");
+
+        let mut migrations: Vec<_> = read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations"))
+            .expect("Couldn't read migrations")
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        migrations.sort();
+
+        let mut seed = Some(sql).filter(|sql| !sql.is_empty());
+        for path in migrations {
+            if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(migration)) {
+                if let Some(sql) = seed.take() {
+                    raw_sql(sql).execute(&db).await.unwrap_or_else(|err| panic!("Failed to run {sql}: {err}"));
+                }
+            }
+
+            let sql = read_to_string(&path).expect("Couldn't read migration");
+            raw_sql(&sql).execute(&db).await.unwrap_or_else(|err| panic!("Failed to apply {}: {err}", path.display()));
+        }
+        assert!(seed.is_none(), "No migration named {migration}");
+
+        TestBot {
+            bot: Bot {
+                db,
+                admins: vec![ADMIN],
+                privileged: vec![ADMIN, HELPER],
+                sheets: SheetBackend::Local(LocalSheets::in_memory()),
+                latest_push: Arc::new(Mutex::new(0)),
+                pending_push: Arc::new(Mutex::new(false)),
+                sheet_layout: default_layout(),
+                sheet_snapshot: Arc::new(Mutex::new(None)),
+                pending_board: Arc::new(Mutex::new(false)),
+                page_cache: Arc::new(Mutex::new(PageCache::new())),
+            },
+            path,
+        }
+    }
+
+    async fn execute(&self, sql: &str) {
+        raw_sql(sql).execute(&self.db).await.unwrap_or_else(|err| panic!("Failed to run {sql}: {err}"));
+    }
+}
+
+impl Deref for TestBot {
+    type Target = Bot;
+
+    fn deref(&self) -> &Bot {
+        &self.bot
+    }
+}
+
+impl Drop for TestBot {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.path.clone().into_os_string();
+            path.push(suffix);
+            let _ = remove_file(path);
+        }
+    }
+}
+
+// Builds a slash command invocation the way Discord would deliver it, with string options
+fn command(name: &str, user: UserId, options: &[(&str, &str)]) -> CommandInteraction {
+    from_value(json!({
+        "id": "1",
+        "application_id": "1",
+        "type": 2,
+        "data": {
+            "id": "1",
+            "name": name,
+            "type": 1,
+            "options": options.iter().map(|(name, value)| json!({ "name": name, "type": 3, "value": value })).collect::<Vec<_>>(),
+        },
+        "channel": null,
+        "channel_id": "1",
+        "user": { "id": user.to_string(), "username": format!("user{user}"), "discriminator": "0", "global_name": null, "avatar": null },
+        "token": "token",
+        "version": 1,
+        "app_permissions": null,
+        "locale": "en-US",
+        "guild_locale": null,
+        "entitlements": [],
+        "context": null,
+    }))
+    .expect("Test commands should deserialize")
+}
@@ -0,0 +1,191 @@
+use serde_json::{json, Value};
+use sqlx::query;
+
+use super::TestBot;
+use crate::{
+    sheet_store::{Dimension, LocalSheets, SheetStore},
+    sheets::parse_layout,
+};
+
+async fn seeded_bot() -> TestBot {
+    let bot = TestBot::new().await;
+    bot.execute(
+        "INSERT INTO players (id, snowflake, name, points) VALUES (1, 3, 'ness', 7), (2, 4, 'lucas', 2);
+        INSERT INTO tracked_worlds (id, tracker_id, name) VALUES (1, 'alpha', 'Alpha'), (2, 'beta', 'Beta');
+        INSERT INTO tracked_slots (id, world, name, games, status, checks, checks_total, free) VALUES
+            (1, 1, 'Ness', 'EarthBound', 1, 0, 10, 0), (2, 1, 'Lucas', 'Mother 3', 0, 0, 10, 1), (3, 1, 'Ninten', 'Mother', 0, 0, 10, 0), (4, 2, 'Paula', 'EarthBound', 0, 0, 10, 0);
+        INSERT INTO claims (slot, player) VALUES (1, 1);
+        UPDATE tracked_worlds SET archived = 1 WHERE id = 2;",
+    )
+    .await;
+    bot
+}
+
+async fn rows(sheets: &impl SheetStore, range: &str) -> Vec<Vec<Value>> {
+    sheets.get(&[range], Dimension::Rows).await.unwrap().remove(0)
+}
+
+#[tokio::test]
+async fn slots_and_players_are_pushed_to_autodata() {
+    let bot = seeded_bot().await;
+    bot.sheets.update("autodata!A1:D", vec![vec![json!("stale"); 4]; 5]).await.unwrap();
+
+    bot.push_needed().await;
+
+    assert_eq!(
+        rows(&bot.sheets, "autodata!A1:D").await,
+        [
+            vec![json!("Alpha"), json!("Ness"), json!("In Progress"), json!("ness")],
+            vec![json!("Alpha"), json!("Lucas"), json!("Unclaimed [Free claim]")],
+            vec![json!("Alpha"), json!("Ninten"), json!("Unclaimed")],
+        ]
+    );
+    assert_eq!(rows(&bot.sheets, "autodata!F1:G").await, [vec![json!("ness"), json!(7)], vec![json!("lucas"), json!(2)]]);
+}
+
+// Skips the one minute throttle between pushes
+async fn push_now(bot: &TestBot) {
+    *bot.latest_push.lock().unwrap() = 0;
+    bot.push_needed().await;
+}
+
+#[tokio::test]
+async fn only_changed_rows_are_pushed() {
+    let bot = seeded_bot().await;
+    push_now(&bot).await;
+
+    // Edits made by hand outside the changed rows survive a push, which shows only those rows were written
+    bot.sheets.update("autodata!A3:D3", vec![vec![json!("edited")]]).await.unwrap();
+    bot.execute("UPDATE tracked_slots SET status = 2 WHERE id = 1; UPDATE players SET points = 9 WHERE id = 2;").await;
+    push_now(&bot).await;
+
+    assert_eq!(
+        rows(&bot.sheets, "autodata!A1:D").await,
+        [
+            vec![json!("Alpha"), json!("Ness"), json!("Goal"), json!("ness")],
+            vec![json!("Alpha"), json!("Lucas"), json!("Unclaimed [Free claim]")],
+            vec![json!("edited"), json!("Ninten"), json!("Unclaimed")],
+        ]
+    );
+    assert_eq!(rows(&bot.sheets, "autodata!F1:G").await, [vec![json!("ness"), json!(7)], vec![json!("lucas"), json!(9)]]);
+}
+
+#[tokio::test]
+async fn tables_are_rewritten_when_rows_are_added_or_removed() {
+    let bot = seeded_bot().await;
+    push_now(&bot).await;
+
+    bot.sheets.update("autodata!A3:D3", vec![vec![json!("edited")]]).await.unwrap();
+    bot.execute("DELETE FROM tracked_slots WHERE id = 2;").await;
+    push_now(&bot).await;
+
+    assert_eq!(
+        rows(&bot.sheets, "autodata!A1:D").await,
+        [
+            vec![json!("Alpha"), json!("Ness"), json!("In Progress"), json!("ness")],
+            vec![json!("Alpha"), json!("Ninten"), json!("Unclaimed")]
+        ]
+    );
+
+    bot.execute("UPDATE tracked_worlds SET archived = 0 WHERE id = 2;").await;
+    push_now(&bot).await;
+
+    assert_eq!(rows(&bot.sheets, "autodata!A1:D").await.len(), 3);
+}
+
+#[tokio::test]
+async fn layouts_choose_columns_and_ranges() {
+    let mut bot = seeded_bot().await;
+    bot.execute(
+        "INSERT INTO realities (id, name, max_claims) VALUES (1, 'Eagleland', 3);
+        UPDATE tracked_worlds SET reality = 1 WHERE id = 1;
+        UPDATE tracked_slots SET checks = 4, last_activity = 90, points = 3 WHERE id = 1;
+        INSERT INTO updates (slot, timestamp, player, description) VALUES (1, 10, 1, 'Onett'), (1, 20, 1, 'Twoson');",
+    )
+    .await;
+    bot.bot.sheet_layout = parse_layout(
+        r#"[
+            { "range": "export!B2", "rows": "slots", "columns": ["reality", "slot", "games", "checks", "points", "last_activity", "latest_update", "player"] },
+            { "range": "export!K2:L", "rows": "players", "columns": ["points", "player"] }
+        ]"#,
+    )
+    .unwrap();
+
+    push_now(&bot).await;
+
+    assert_eq!(
+        rows(&bot.sheets, "export!B2:I").await,
+        [
+            vec![
+                json!("Eagleland"),
+                json!("Ness"),
+                json!("EarthBound"),
+                json!("4/10"),
+                json!(3),
+                json!(90),
+                json!("Twoson"),
+                json!("ness")
+            ],
+            vec![json!("Eagleland"), json!("Lucas"), json!("Mother 3"), json!("0/10"), json!(0)],
+            vec![json!("Eagleland"), json!("Ninten"), json!("Mother"), json!("0/10"), json!(0)],
+        ]
+    );
+    assert_eq!(rows(&bot.sheets, "export!K2:L").await, [vec![json!(7), json!("ness")], vec![json!(2), json!("lucas")]]);
+    assert!(rows(&bot.sheets, "autodata!A1:G").await.is_empty());
+}
+
+#[test]
+fn invalid_layouts_are_rejected() {
+    for layout in [
+        r#"[{ "range": "autodata!A1", "rows": "slots", "columns": ["colour"] }]"#,
+        r#"[{ "range": "autodata!A1", "rows": "slots", "columns": [] }]"#,
+        r#"[{ "range": "A1", "rows": "slots", "columns": ["world"] }]"#,
+        r#"[{ "range": "autodata!A1", "rows": "players", "columns": ["player", "games"] }]"#,
+    ] {
+        assert!(parse_layout(layout).is_err(), "{layout} should be rejected");
+    }
+}
+
+#[tokio::test]
+async fn unspent_points_are_read_from_archivists() {
+    let bot = seeded_bot().await;
+    bot.sheets
+        .update(
+            "Archivists!A1:D",
+            vec![vec![json!("Name"), json!(""), json!(""), json!("Unspent")], vec![json!("Ness"), json!(""), json!(""), json!("5")]],
+        )
+        .await
+        .unwrap();
+    bot.sheets
+        .update(
+            "Archivists!A3:D",
+            vec![vec![json!("LUCAS"), json!(""), json!(""), json!(12)], vec![json!("Ninten"), json!(""), json!(""), json!("lots")]],
+        )
+        .await
+        .unwrap();
+
+    bot.update_unspent_points().await;
+
+    let players = query!("SELECT name, unspent_points FROM players ORDER BY id").fetch_all(&bot.db).await.unwrap();
+    assert_eq!(
+        players.iter().map(|player| (player.name.as_str(), player.unspent_points)).collect::<Vec<_>>(),
+        [("ness", 5), ("lucas", 12)]
+    );
+}
+
+#[tokio::test]
+async fn local_sheets_follow_a1_ranges() {
+    let sheets = LocalSheets::in_memory();
+    sheets.update("data!B2:C", vec![vec![json!(1), json!(2)], vec![json!(3), json!(4)], vec![json!(5)]]).await.unwrap();
+    sheets.clear("data!C3:C").await.unwrap();
+
+    assert_eq!(
+        rows(&sheets, "data!A1:C").await,
+        [vec![], vec![Value::Null, json!(1), json!(2)], vec![Value::Null, json!(3)], vec![Value::Null, json!(5)]]
+    );
+    assert_eq!(
+        sheets.get(&["data!C1:C", "data!A1:A", "other!A1:B"], Dimension::Columns).await.unwrap(),
+        [vec![vec![Value::Null, json!(2)]], vec![], vec![]]
+    );
+    assert!(sheets.update("data1:C", vec![]).await.is_err());
+}
@@ -0,0 +1,352 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string};
+use sqlx::{query, query_as, SqliteConnection};
+
+use crate::Bot;
+
+const UNDO_WINDOW: i64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum UndoAction {
+    Unclaim {
+        claims: Vec<ClaimRow>,
+        #[serde(default)]
+        released: Vec<i64>,
+    },
+    MarkFree {
+        slot: i64,
+        free: i64,
+    },
+    CancelPreclaims {
+        world: PreclaimWorld,
+    },
+    FinishWorld {
+        world: i64,
+        awards: Vec<Award>,
+        preclaim_world: Option<PreclaimWorld>,
+        #[serde(default)]
+        season: Option<i64>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ClaimRow {
+    pub slot: i64,
+    pub player: i64,
+    pub public: Option<String>,
+    #[serde(default)]
+    pub public_kind: i64,
+    #[serde(default)]
+    pub public_expires: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PreclaimWorld {
+    name: String,
+    preclaim_end: i64,
+    resolved_preclaims: i64,
+    reality: Option<i64>,
+    slots: Vec<PreclaimSlot>,
+    preclaims: Vec<PreclaimRow>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PreclaimSlot {
+    id: i64,
+    name: String,
+    games: String,
+    notes: String,
+    points: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PreclaimRow {
+    slot: i64,
+    player: i64,
+    status: i64,
+    blocked_by_unspent: i64,
+}
+
+struct UndoRecord {
+    id: i64,
+    description: String,
+    payload: String,
+    timestamp: i64,
+    undone: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Award {
+    pub slot: i64,
+    pub claimant: i64,
+    pub recipient: i64,
+    pub points: i64,
+    pub completed: bool,
+    pub goal: bool,
+}
+
+impl UndoAction {
+    pub async fn record(&self, connection: &mut SqliteConnection, actor: i64, command: &str, description: &str) -> Result<(), sqlx::Error> {
+        let payload = to_string(self).map_err(|err| sqlx::Error::Encode(Box::new(err)))?;
+        query!("INSERT INTO undo_log (actor, command, description, payload) VALUES (?, ?, ?, ?)", actor, command, description, payload)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn restore(self, connection: &mut SqliteConnection) -> Result<(), String> {
+        match self {
+            UndoAction::Unclaim { claims, released } => {
+                for claim in claims {
+                    if query!("SELECT id FROM claims WHERE slot = ? LIMIT 1", claim.slot)
+                        .fetch_optional(&mut *connection)
+                        .await
+                        .map_err(|_| "Failed to get claim status for slot")?
+                        .is_some()
+                    {
+                        return Err(String::from("The slot has been claimed again since"));
+                    }
+
+                    query!(
+                        "INSERT INTO claims (slot, player, public, public_kind, public_expires, timestamp) VALUES (?, ?, ?, ?, ?, ?)",
+                        claim.slot,
+                        claim.player,
+                        claim.public,
+                        claim.public_kind,
+                        claim.public_expires,
+                        claim.timestamp
+                    )
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|_| "Failed to restore claim")?;
+                }
+
+                for id in released {
+                    query!("DELETE FROM released_claims WHERE id = ?", id)
+                        .execute(&mut *connection)
+                        .await
+                        .map_err(|_| "Failed to clear unclaim cooldown")?;
+                }
+            }
+            UndoAction::MarkFree { slot, free } => {
+                query!("UPDATE tracked_slots SET free = ? WHERE id = ?", free, slot)
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|_| "Failed to restore free claim status")?;
+            }
+            UndoAction::CancelPreclaims { world } => world.restore(connection).await?,
+            UndoAction::FinishWorld {
+                world,
+                awards,
+                preclaim_world,
+                season,
+            } => {
+                // Season points were snapshotted into the standings when the season ended, so they can't be taken back
+                let current_season = query!("SELECT MAX(id) AS season FROM seasons")
+                    .fetch_one(&mut *connection)
+                    .await
+                    .map_err(|_| "Failed to get current season")?
+                    .season;
+                if current_season != season {
+                    return Err(String::from("A new season has started since the world was finished"));
+                }
+
+                if query!("UPDATE tracked_worlds SET archived = 0, archived_at = NULL WHERE id = ? AND archived = 1", world)
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|_| "Failed to unarchive world")?
+                    .rows_affected()
+                    == 0
+                {
+                    return Err(String::from("The world is no longer archived"));
+                }
+
+                for award in awards {
+                    query!(
+                        "UPDATE players SET points = points - ?, season_points = MAX(season_points - ?, 0) WHERE id = ?",
+                        award.points,
+                        award.points,
+                        award.recipient
+                    )
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|_| "Failed to revoke points")?;
+
+                    query!(
+                        "UPDATE players SET slots_completed = slots_completed - ?, goals_reached = goals_reached - ? WHERE id = ?",
+                        award.completed,
+                        award.goal,
+                        award.claimant
+                    )
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|_| "Failed to revert completion stats")?;
+
+                    query!("UPDATE tracked_slots SET awarded_to = NULL WHERE id = ?", award.slot)
+                        .execute(&mut *connection)
+                        .await
+                        .map_err(|_| "Failed to clear points recipient")?;
+                }
+
+                if let Some(preclaim_world) = preclaim_world {
+                    preclaim_world.restore(connection).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PreclaimWorld {
+    pub async fn fetch(connection: &mut SqliteConnection, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let Some(world) = query!("SELECT id, preclaim_end, resolved_preclaims, reality FROM worlds WHERE name = ? LIMIT 1", name)
+            .fetch_optional(&mut *connection)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let slots = query_as!(PreclaimSlot, "SELECT id, name, games, notes, points FROM slots WHERE world = ?", world.id)
+            .fetch_all(&mut *connection)
+            .await?;
+        let preclaims = query_as!(
+            PreclaimRow,
+            "SELECT slot, player, status, blocked_by_unspent FROM preclaims WHERE slot IN (SELECT id FROM slots WHERE world = ?)",
+            world.id
+        )
+        .fetch_all(&mut *connection)
+        .await?;
+
+        Ok(Some(PreclaimWorld {
+            name: name.to_owned(),
+            preclaim_end: world.preclaim_end,
+            resolved_preclaims: world.resolved_preclaims,
+            reality: world.reality,
+            slots,
+            preclaims,
+        }))
+    }
+
+    async fn restore(self, connection: &mut SqliteConnection) -> Result<(), String> {
+        if query!("SELECT id FROM worlds WHERE name = ? LIMIT 1", self.name)
+            .fetch_optional(&mut *connection)
+            .await
+            .map_err(|_| "Failed to get preclaim world")?
+            .is_some()
+        {
+            return Err(format!("A preclaim world named {} has been created since", self.name));
+        }
+
+        let world_id = query!(
+            "INSERT INTO worlds (name, preclaim_end, resolved_preclaims, reality) VALUES (?, ?, ?, ?) RETURNING id",
+            self.name,
+            self.preclaim_end,
+            self.resolved_preclaims,
+            self.reality
+        )
+        .fetch_one(&mut *connection)
+        .await
+        .map_err(|_| "Failed to restore preclaim world")?
+        .id;
+
+        let mut slot_ids = HashMap::new();
+        for slot in self.slots {
+            let slot_id = query!(
+                "INSERT INTO slots (world, name, games, notes, points) VALUES (?, ?, ?, ?, ?) RETURNING id",
+                world_id,
+                slot.name,
+                slot.games,
+                slot.notes,
+                slot.points
+            )
+            .fetch_one(&mut *connection)
+            .await
+            .map_err(|_| "Failed to restore preclaim slot")?
+            .id;
+            slot_ids.insert(slot.id, slot_id);
+        }
+
+        for preclaim in self.preclaims {
+            let Some(slot_id) = slot_ids.get(&preclaim.slot) else {
+                continue;
+            };
+
+            query!(
+                "INSERT INTO preclaims (slot, player, status, blocked_by_unspent) VALUES (?, ?, ?, ?)",
+                slot_id,
+                preclaim.player,
+                preclaim.status,
+                preclaim.blocked_by_unspent
+            )
+            .execute(&mut *connection)
+            .await
+            .map_err(|_| "Failed to restore preclaim")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Bot {
+    // Only admins can revert entries recorded by someone else, since those may come from admin only commands
+    pub async fn undo(&self, actor: i64, admin: bool, entry: Option<i64>) -> Result<String, String> {
+        let record = if let Some(entry) = entry {
+            query_as!(
+                UndoRecord,
+                "SELECT id, description, payload, timestamp, undone FROM undo_log WHERE id = ? AND (? OR actor = ?) LIMIT 1",
+                entry,
+                admin,
+                actor
+            )
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|_| "Failed to get undo entry")?
+        } else {
+            query_as!(
+                UndoRecord,
+                "SELECT id, description, payload, timestamp, undone FROM undo_log WHERE actor = ? AND undone = 0 ORDER BY id DESC LIMIT 1",
+                actor
+            )
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|_| "Failed to get undo entry")?
+        };
+
+        let Some(record) = record else {
+            return Err(String::from("There is nothing to undo"));
+        };
+
+        if record.undone > 0 {
+            return Err(String::from("This action has already been undone"));
+        }
+
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        if record.timestamp < current_time.as_secs() as i64 - UNDO_WINDOW {
+            return Err(String::from("This action is too old to be undone"));
+        }
+
+        let action: UndoAction = from_str(&record.payload).map_err(|_| "Malformed undo entry")?;
+
+        let mut transaction = self.db.begin().await.map_err(|_| "Failed to create transaction")?;
+
+        action.restore(&mut transaction).await?;
+
+        query!("UPDATE undo_log SET undone = 1 WHERE id = ?", record.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| "Failed to mark action as undone")?;
+
+        transaction.commit().await.map_err(|_| "Failed to commit transaction")?;
+
+        Ok(record.description)
+    }
+}
@@ -1,21 +1,43 @@
-use serenity::all::{CommandInteraction, ComponentInteraction, Context, CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::all::{CommandInteraction, ComponentInteraction, CreateInteractionResponse, CreateInteractionResponseMessage, ModalInteraction};
+
+use crate::discord::Discord;
 
 pub trait SimpleReply {
-    async fn simple_reply<T: Into<String>>(&self, _ctx: &Context, _content: T);
+    async fn simple_reply<T: Into<String>>(&self, _ctx: &impl Discord, _content: T);
 }
 
 impl SimpleReply for CommandInteraction {
-    async fn simple_reply<T: Into<String>>(&self, ctx: &Context, content: T) {
-        let _ = self
-            .create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().ephemeral(true).content(content)))
+    async fn simple_reply<T: Into<String>>(&self, ctx: &impl Discord, content: T) {
+        let _ = ctx
+            .create_response(
+                self.id,
+                &self.token,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().ephemeral(true).content(content)),
+            )
             .await;
     }
 }
 
 impl SimpleReply for ComponentInteraction {
-    async fn simple_reply<T: Into<String>>(&self, ctx: &Context, content: T) {
-        let _ = self
-            .create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().ephemeral(true).content(content)))
+    async fn simple_reply<T: Into<String>>(&self, ctx: &impl Discord, content: T) {
+        let _ = ctx
+            .create_response(
+                self.id,
+                &self.token,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().ephemeral(true).content(content)),
+            )
+            .await;
+    }
+}
+
+impl SimpleReply for ModalInteraction {
+    async fn simple_reply<T: Into<String>>(&self, ctx: &impl Discord, content: T) {
+        let _ = ctx
+            .create_response(
+                self.id,
+                &self.token,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().ephemeral(true).content(content)),
+            )
             .await;
     }
 }